@@ -0,0 +1,2 @@
+-- Add migration script here
+CREATE INDEX IF NOT EXISTS idx_comments_annotations_gin ON comments USING GIN (annotations jsonb_path_ops);
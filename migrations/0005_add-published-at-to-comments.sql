@@ -0,0 +1,17 @@
+-- Add migration script here
+ALTER TABLE comments ADD COLUMN published_at TIMESTAMPTZ;
+
+-- Existing rows were extracted at roughly created_at, so anchor the relative time there.
+-- "a day ago" and "an hour ago" count as one, the same as the parser used at ingestion
+UPDATE comments
+SET published_at = created_at - (((CASE WHEN m[1] IN ('a', 'an') THEN '1' ELSE m[1] END) || ' ' || m[2])::interval)
+FROM (
+    SELECT id AS comment_pk,
+           regexp_match(lower(published_time), '\m(\d+|an?)\s+(second|minute|hour|day|week|month|year)s?\s+ago') AS m
+    FROM comments
+) parsed
+WHERE comments.id = parsed.comment_pk
+  AND parsed.m IS NOT NULL
+  AND comments.created_at IS NOT NULL;
+
+CREATE INDEX idx_comments_published_at ON comments(video_id, published_at);
@@ -0,0 +1,48 @@
+-- Add migration script here
+ALTER TABLE comments ADD COLUMN published_edited BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE comments ADD COLUMN published_precision VARCHAR;
+
+ALTER TABLE video_info ADD COLUMN uploaded_at TIMESTAMPTZ;
+ALTER TABLE video_info ADD COLUMN upload_date_precision VARCHAR;
+
+UPDATE comments
+SET published_edited = lower(published_time) LIKE '%(edited)%',
+    published_precision = (regexp_match(lower(published_time), '\m(?:\d+|an?)\s+(second|minute|hour|day|week|month|year)s?\s+ago'))[1]
+WHERE published_time IS NOT NULL;
+
+-- Relative upload dates are anchored at when the video was first extracted
+UPDATE video_info
+SET uploaded_at = created_at - (((CASE WHEN m[1] IN ('a', 'an') THEN '1' ELSE m[1] END) || ' ' || m[2])::interval),
+    upload_date_precision = m[2]
+FROM (
+    SELECT id AS video_pk,
+           regexp_match(lower(upload_date), '\m(\d+|an?)\s+(second|minute|hour|day|week|month|year)s?\s+ago') AS m
+    FROM video_info
+) parsed
+WHERE video_info.id = parsed.video_pk
+  AND parsed.m IS NOT NULL
+  AND video_info.created_at IS NOT NULL;
+
+UPDATE video_info
+SET uploaded_at = to_date(m[1], 'Mon DD, YYYY')::timestamp AT TIME ZONE 'UTC',
+    upload_date_precision = 'day'
+FROM (
+    SELECT id AS video_pk,
+           regexp_match(upload_date, '([A-Z][a-z]{2} \d{1,2}, \d{4})') AS m
+    FROM video_info
+) parsed
+WHERE video_info.id = parsed.video_pk
+  AND parsed.m IS NOT NULL
+  AND video_info.uploaded_at IS NULL;
+
+UPDATE video_info
+SET uploaded_at = m[1]::date::timestamp AT TIME ZONE 'UTC',
+    upload_date_precision = 'day'
+FROM (
+    SELECT id AS video_pk,
+           regexp_match(upload_date, '(\d{4}-\d{2}-\d{2})') AS m
+    FROM video_info
+) parsed
+WHERE video_info.id = parsed.video_pk
+  AND parsed.m IS NOT NULL
+  AND video_info.uploaded_at IS NULL;
@@ -0,0 +1,12 @@
+CREATE TABLE comment_sentiment (
+    comment_id VARCHAR PRIMARY KEY,
+    label VARCHAR NOT NULL,
+    polarity REAL NOT NULL,
+    scores JSONB NOT NULL DEFAULT '{}'::jsonb,
+    emotions JSONB NOT NULL DEFAULT '{}'::jsonb,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (comment_id) REFERENCES comments(comment_id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_comment_sentiment_label ON comment_sentiment(label);
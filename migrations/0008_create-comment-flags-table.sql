@@ -0,0 +1,13 @@
+CREATE TABLE comment_flags (
+    id SERIAL PRIMARY KEY,
+    comment_id VARCHAR NOT NULL,
+    source VARCHAR NOT NULL,
+    reason VARCHAR NOT NULL,
+    score REAL,
+    detail TEXT,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (comment_id, source, reason),
+    FOREIGN KEY (comment_id) REFERENCES comments(comment_id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_comment_flags_comment_id ON comment_flags(comment_id);
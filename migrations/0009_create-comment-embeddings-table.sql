@@ -0,0 +1,12 @@
+CREATE EXTENSION IF NOT EXISTS vector;
+
+-- The dimension matches the default embedding model (all-MiniLM-L6-v2); switching to a model
+-- with a different output size needs a new migration
+CREATE TABLE comment_embeddings (
+    comment_id VARCHAR PRIMARY KEY,
+    model VARCHAR,
+    embedding vector(384) NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (comment_id) REFERENCES comments(comment_id) ON DELETE CASCADE
+);
@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS idx_comment_embeddings_hnsw ON comment_embeddings USING hnsw (embedding vector_cosine_ops);
@@ -0,0 +1,13 @@
+CREATE TABLE video_summaries (
+    id SERIAL PRIMARY KEY,
+    video_id VARCHAR NOT NULL,
+    model VARCHAR,
+    prompt_version VARCHAR NOT NULL,
+    summary TEXT NOT NULL,
+    source_comment_ids TEXT[] NOT NULL DEFAULT '{}',
+    token_budget INTEGER NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (video_id) REFERENCES video_info(yt_id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_video_summaries_video_id ON video_summaries(video_id, created_at DESC);
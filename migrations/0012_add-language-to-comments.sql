@@ -0,0 +1,5 @@
+-- Detected at ingestion; NULL when the detector isn't confident (typically very short comments)
+ALTER TABLE comments ADD COLUMN language VARCHAR;
+ALTER TABLE comments ADD COLUMN language_confidence REAL;
+
+CREATE INDEX idx_comments_language ON comments(video_id, language);
@@ -0,0 +1,21 @@
+CREATE TABLE ner_presets (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR UNIQUE NOT NULL,
+    description TEXT,
+    labels TEXT[] NOT NULL,
+    threshold REAL NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Which presets a comment has been annotated with, so rankings can be narrowed to one preset's labels
+CREATE TABLE comment_annotation_presets (
+    comment_id VARCHAR NOT NULL,
+    preset_id INTEGER NOT NULL,
+    annotated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (comment_id, preset_id),
+    FOREIGN KEY (comment_id) REFERENCES comments(comment_id) ON DELETE CASCADE,
+    FOREIGN KEY (preset_id) REFERENCES ner_presets(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_comment_annotation_presets_preset_id ON comment_annotation_presets(preset_id);
@@ -0,0 +1,24 @@
+-- Human corrections to NER output. There's deliberately no foreign key to comments: refreshing a
+-- video deletes and re-inserts its comments, and corrections and their history must survive that.
+CREATE TABLE annotation_overrides (
+    comment_id VARCHAR NOT NULL,
+    label VARCHAR NOT NULL,
+    text VARCHAR NOT NULL,
+    action VARCHAR NOT NULL CHECK (action IN ('add', 'remove')),
+    editor VARCHAR NOT NULL,
+    updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (comment_id, label, text)
+);
+
+CREATE TABLE annotation_edits (
+    id SERIAL PRIMARY KEY,
+    comment_id VARCHAR NOT NULL,
+    label VARCHAR NOT NULL,
+    text VARCHAR NOT NULL,
+    action VARCHAR NOT NULL CHECK (action IN ('add', 'remove')),
+    editor VARCHAR NOT NULL,
+    note TEXT,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_annotation_edits_comment_id ON annotation_edits(comment_id, created_at);
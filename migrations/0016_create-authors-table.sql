@@ -0,0 +1,21 @@
+-- One row per commenter. Like annotation overrides there's no foreign key to comments, so an
+-- author's first sighting survives their comments being deleted by a refresh.
+CREATE TABLE authors (
+    channel_id VARCHAR PRIMARY KEY,
+    display_name VARCHAR NOT NULL,
+    thumbnail TEXT,
+    user_verified BOOLEAN,
+    first_seen_at TIMESTAMPTZ NOT NULL,
+    last_seen_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Backfill from comments ingested before this table existed
+INSERT INTO authors (channel_id, display_name, thumbnail, user_verified, first_seen_at, last_seen_at)
+SELECT DISTINCT ON (channel_id)
+       channel_id, display_name, thumbnail, user_verified,
+       MIN(COALESCE(published_at, created_at, CURRENT_TIMESTAMP)) OVER (PARTITION BY channel_id),
+       MAX(COALESCE(published_at, created_at, CURRENT_TIMESTAMP)) OVER (PARTITION BY channel_id)
+FROM comments
+ORDER BY channel_id, created_at DESC;
@@ -0,0 +1,25 @@
+-- Entity filters match with `annotations @> {"label": ["text"]}` so they can use the GIN index, which
+-- needs every label lowercase and every value an array. Older rows kept the model's label casing and
+-- some stored a single entity as a plain string; labels differing only in case are merged.
+UPDATE comments
+SET annotations = normalised.annotations
+FROM (
+    SELECT c.id AS comment_pk, jsonb_object_agg(labels.label, labels.texts) AS annotations
+    FROM comments c
+    CROSS JOIN LATERAL (
+        SELECT lower(a.key) AS label, jsonb_agg(DISTINCT t.value) AS texts
+        FROM jsonb_each(c.annotations) AS a
+        CROSS JOIN LATERAL jsonb_array_elements(
+            CASE WHEN jsonb_typeof(a.value) = 'array' THEN a.value ELSE jsonb_build_array(a.value) END
+        ) AS t
+        WHERE jsonb_typeof(t.value) <> 'null'
+        GROUP BY lower(a.key)
+    ) labels
+    WHERE jsonb_typeof(c.annotations) = 'object'
+      AND EXISTS (
+          SELECT 1 FROM jsonb_each(c.annotations) AS a
+          WHERE a.key <> lower(a.key) OR jsonb_typeof(a.value) <> 'array'
+      )
+    GROUP BY c.id
+) normalised
+WHERE comments.id = normalised.comment_pk;
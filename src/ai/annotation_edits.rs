@@ -0,0 +1,176 @@
+use std::collections::{HashMap, HashSet};
+use axum::extract::State;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::PgPool;
+use crate::db::{
+    connection::AppState,
+    models::{Comment, AnnotationEdit, AnnotationOverride},
+    operations::{CommentRepository, AnnotationEditRepository}
+};
+use crate::ai::ner::{build_db_json_as_annotations, AnnotationObject, Annotations};
+use crate::routes::errors::AppError;
+
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationAction {
+    Add,
+    Remove
+}
+
+impl AnnotationAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnnotationAction::Add => "add",
+            AnnotationAction::Remove => "remove"
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "add" => Some(AnnotationAction::Add),
+            "remove" => Some(AnnotationAction::Remove),
+            _ => None
+        }
+    }
+}
+
+// `add` and `remove` map labels to entity texts, in the same shape as stored annotations
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AnnotationEditRequest {
+    pub editor: String,
+    #[serde(default)]
+    pub add: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub remove: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub note: Option<String>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnotationChange {
+    pub label: String,
+    pub text: String,
+    pub action: AnnotationAction
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AnnotationHistory {
+    pub comment_id: String,
+    pub overrides: Vec<AnnotationOverride>,
+    pub edits: Vec<AnnotationEdit>
+}
+
+fn collect_changes(entities: &HashMap<String, Vec<String>>, action: AnnotationAction) -> Vec<AnnotationChange> {
+    entities.iter()
+        .flat_map(|(label, texts)| texts.iter().map(move |text| (label, text)))
+        .map(|(label, text)| AnnotationChange {
+            label: label.trim().to_lowercase(),
+            text: text.trim().to_string(),
+            action
+        })
+        .filter(|change| !change.label.is_empty() && !change.text.is_empty())
+        .collect()
+}
+
+fn build_changes(edit_request: &AnnotationEditRequest) -> Result<Vec<AnnotationChange>, AppError> {
+    let mut changes = collect_changes(&edit_request.add, AnnotationAction::Add);
+    changes.extend(collect_changes(&edit_request.remove, AnnotationAction::Remove));
+
+    let mut seen: HashSet<(&str, &str)> = HashSet::new();
+    for change in &changes {
+        if !seen.insert((&change.label, &change.text)) {
+            return Err(AppError::InvalidInput(format!("'{}:{}' appears more than once in the edit", change.label, change.text)));
+        }
+    }
+
+    Ok(changes)
+}
+
+pub async fn edit_annotations(comment_id: &str, edit_request: AnnotationEditRequest, State(app_state): State<AppState>) -> Result<Comment, AppError> {
+    let editor = edit_request.editor.trim();
+    if editor.is_empty() {
+        return Err(AppError::InvalidInput("Editor cannot be empty".to_string()));
+    }
+
+    let changes = build_changes(&edit_request)?;
+    if changes.is_empty() {
+        return Err(AppError::InvalidInput("Provide at least one entity to add or remove".to_string()));
+    }
+
+    let comment = CommentRepository::get_by_comment_id(&app_state.db_pool, comment_id).await?
+        .ok_or_else(|| AppError::InvalidInput("Comment not found".to_string()))?;
+
+    let mut annotations: Annotations = build_db_json_as_annotations(std::slice::from_ref(&comment))
+        .pop()
+        .map(|ann_obj| ann_obj.annotations)
+        .unwrap_or_default();
+    for change in &changes {
+        match change.action {
+            AnnotationAction::Add => annotations.insert(&change.label, &change.text),
+            AnnotationAction::Remove => annotations.remove(&change.label, &change.text)
+        }
+    }
+
+    AnnotationEditRepository::apply(
+        &app_state.db_pool,
+        comment_id,
+        editor,
+        edit_request.note.clone(),
+        &changes,
+        json!(annotations)
+    ).await
+}
+
+pub async fn get_annotation_history(comment_id: &str, State(app_state): State<AppState>) -> Result<AnnotationHistory, AppError> {
+    let overrides = AnnotationEditRepository::get_overrides(&app_state.db_pool, &[comment_id.to_string()]).await?;
+    let edits = AnnotationEditRepository::get_history(&app_state.db_pool, comment_id).await?;
+
+    Ok(AnnotationHistory {
+        comment_id: comment_id.to_string(),
+        overrides,
+        edits
+    })
+}
+
+// Annotations for the comments that have stored overrides, with those overrides applied on top
+// of whatever is stored. Applying them is idempotent, so corrected rows come out unchanged.
+pub fn with_overrides(comments: &[Comment], overrides: &[AnnotationOverride]) -> Vec<AnnotationObject> {
+    let mut overrides_by_comment: HashMap<&str, Vec<&AnnotationOverride>> = HashMap::new();
+    for annotation_override in overrides {
+        overrides_by_comment.entry(annotation_override.comment_id.as_str()).or_default().push(annotation_override);
+    }
+
+    comments.iter()
+        .filter_map(|comment| {
+            let comment_overrides = overrides_by_comment.get(comment.comment_id.as_str())?;
+            let mut annotations: Annotations = build_db_json_as_annotations(std::slice::from_ref(comment))
+                .pop()
+                .map(|ann_obj| ann_obj.annotations)
+                .unwrap_or_default();
+            annotations.apply_overrides(comment_overrides.iter().copied());
+            Some(AnnotationObject { id: comment.comment_id.clone(), annotations })
+        })
+        .collect()
+}
+
+// Refreshing a video deletes and re-inserts its comments with empty annotations, so stored
+// corrections are written back onto the new rows. Returns the comments as they are now stored.
+pub async fn reapply_overrides(pool: &PgPool, comments: Vec<Comment>) -> Result<Vec<Comment>, AppError> {
+    let comment_ids: Vec<String> = comments.iter().map(|comment| comment.comment_id.clone()).collect();
+    let overrides = AnnotationEditRepository::get_overrides(pool, &comment_ids).await?;
+    if overrides.is_empty() {
+        return Ok(comments);
+    }
+
+    let corrected = with_overrides(&comments, &overrides);
+    let mut updated: HashMap<String, Comment> = CommentRepository::update_annotations(pool, corrected).await?
+        .into_iter()
+        .map(|comment| (comment.comment_id.clone(), comment))
+        .collect();
+
+    Ok(comments.into_iter()
+        .map(|comment| updated.remove(&comment.comment_id).unwrap_or(comment))
+        .collect())
+}
@@ -0,0 +1,202 @@
+use std::collections::{BTreeSet, HashMap};
+use axum::extract::State;
+use serde::{Deserialize, Serialize};
+use crate::db::{
+    connection::AppState,
+    models::{AnnotationScope, SharedCommenter, VideoCommentStats, VideoInfo},
+    operations::{VideoInfoRepository, CommentRepository, AnalyticsRepository}
+};
+use crate::routes::errors::AppError;
+
+
+const MAX_COMPARED_VIDEOS: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompareRequest {
+    pub video_ids: Vec<String>,
+    // Minimum mentions for an entity to count as one of a video's top entities
+    #[serde(default)]
+    pub threshold: Option<u32>,
+    // Top entities kept per label, per video
+    #[serde(default)]
+    pub entity_limit: Option<u32>,
+    #[serde(default)]
+    pub commenter_limit: Option<i64>,
+    #[serde(default)]
+    pub exclude_flagged: bool
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VideoEngagement {
+    pub reported_comments: i64,
+    pub scraped_comments: i64,
+    pub scraped_ratio: Option<f64>,
+    pub top_level_comments: i64,
+    pub replies: i64,
+    pub unique_authors: i64,
+    pub verified_share: Option<f64>,
+    pub creator_replies: i64,
+    pub total_likes: i64,
+    pub likes_per_comment: Option<f64>,
+    pub median_likes: Option<f64>,
+    pub average_length: Option<f64>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EntityKey {
+    pub label: String,
+    pub text: String
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComparedEntity {
+    pub label: String,
+    pub text: String,
+    pub count: i64
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComparedVideo {
+    pub video: VideoInfo,
+    pub engagement: VideoEngagement,
+    pub top_entities: Vec<ComparedEntity>
+}
+
+// Jaccard similarities are null when neither video has anything to compare
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VideoPair {
+    pub video_a: String,
+    pub video_b: String,
+    pub shared_entities: Vec<EntityKey>,
+    pub entity_jaccard: Option<f64>,
+    pub shared_commenters: i64,
+    pub commenter_jaccard: Option<f64>
+}
+
+// `videos` keeps the order of the request so metrics line up column by column
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VideoComparison {
+    pub videos: Vec<ComparedVideo>,
+    pub pairs: Vec<VideoPair>,
+    pub entities_in_all: Vec<EntityKey>,
+    pub shared_commenters: Vec<SharedCommenter>
+}
+
+fn ratio(part: f64, whole: f64) -> Option<f64> {
+    (whole > 0.0).then(|| part / whole)
+}
+
+fn engagement(video: &VideoInfo, stats: &VideoCommentStats) -> VideoEngagement {
+    VideoEngagement {
+        reported_comments: video.comment_count,
+        scraped_comments: stats.scraped_comments,
+        scraped_ratio: ratio(stats.scraped_comments as f64, video.comment_count as f64),
+        top_level_comments: stats.top_level_comments,
+        replies: stats.replies,
+        unique_authors: stats.unique_authors,
+        verified_share: ratio(stats.verified_authors as f64, stats.unique_authors as f64),
+        creator_replies: stats.creator_replies,
+        total_likes: stats.total_likes,
+        likes_per_comment: ratio(stats.total_likes as f64, stats.scraped_comments as f64),
+        median_likes: stats.likes_p50,
+        average_length: stats.average_length
+    }
+}
+
+fn entity_keys(video: &ComparedVideo) -> BTreeSet<EntityKey> {
+    video.top_entities.iter()
+        .map(|entity| EntityKey { label: entity.label.clone(), text: entity.text.clone() })
+        .collect()
+}
+
+fn validate_request(compare_request: &CompareRequest) -> Result<Vec<String>, AppError> {
+    let mut video_ids: Vec<String> = Vec::new();
+    for video_id in compare_request.video_ids.iter().map(|id| id.trim()).filter(|id| !id.is_empty()) {
+        if !video_ids.iter().any(|seen| seen == video_id) {
+            video_ids.push(video_id.to_string());
+        }
+    }
+
+    if video_ids.len() < 2 {
+        return Err(AppError::InvalidInput("Provide at least two distinct video_ids to compare".to_string()));
+    }
+    if video_ids.len() > MAX_COMPARED_VIDEOS {
+        return Err(AppError::InvalidInput(format!("At most {} videos can be compared at once", MAX_COMPARED_VIDEOS)));
+    }
+    Ok(video_ids)
+}
+
+pub async fn compare_videos(compare_request: &CompareRequest, State(app_state): State<AppState>) -> Result<VideoComparison, AppError> {
+    let video_ids = validate_request(compare_request)?;
+    let threshold = compare_request.threshold.unwrap_or(2) as i64;
+    let entity_limit = compare_request.entity_limit.unwrap_or(10) as i64;
+    let commenter_limit = compare_request.commenter_limit.unwrap_or(50).clamp(1, 500);
+
+    let mut videos = Vec::with_capacity(video_ids.len());
+    for video_id in &video_ids {
+        let video = VideoInfoRepository::get_by_yt_id(&app_state.db_pool, video_id).await?
+            .ok_or_else(|| AppError::InvalidInput(format!("Video '{}' not found", video_id)))?;
+
+        let stats = AnalyticsRepository::get_comment_stats(&app_state.db_pool, video_id, &video.channel_id, compare_request.exclude_flagged).await?;
+
+        let scope = AnnotationScope {
+            video_ids: Some(vec![video_id.clone()]),
+            exclude_flagged: compare_request.exclude_flagged,
+            ..Default::default()
+        };
+        let top_entities = CommentRepository::get_ranked_annotations(&app_state.db_pool, &scope, threshold, entity_limit).await?
+            .into_iter()
+            .map(|row| ComparedEntity { label: row.label, text: row.text, count: row.count })
+            .collect();
+
+        videos.push(ComparedVideo {
+            engagement: engagement(&video, &stats),
+            video,
+            top_entities
+        });
+    }
+
+    let commenter_pairs: HashMap<(String, String), i64> = AnalyticsRepository::get_shared_commenter_pairs(&app_state.db_pool, &video_ids, compare_request.exclude_flagged).await?
+        .into_iter()
+        .map(|pair| ((pair.video_a, pair.video_b), pair.shared))
+        .collect();
+    let shared_commenters = AnalyticsRepository::get_shared_commenters(&app_state.db_pool, &video_ids, commenter_limit, compare_request.exclude_flagged).await?;
+
+    let entity_sets: Vec<BTreeSet<EntityKey>> = videos.iter().map(entity_keys).collect();
+
+    let mut pairs = Vec::new();
+    for (i, a) in videos.iter().enumerate() {
+        for (j, b) in videos.iter().enumerate().skip(i + 1) {
+            let (a_id, b_id) = (&a.video.yt_id, &b.video.yt_id);
+            let shared_entities: Vec<EntityKey> = entity_sets[i].intersection(&entity_sets[j]).cloned().collect();
+            let entity_union = entity_sets[i].union(&entity_sets[j]).count();
+
+            // The pair query orders its ids, so look the pair up both ways round
+            let shared_commenters = commenter_pairs.get(&(a_id.clone(), b_id.clone()))
+                .or_else(|| commenter_pairs.get(&(b_id.clone(), a_id.clone())))
+                .copied()
+                .unwrap_or(0);
+            let commenter_union = a.engagement.unique_authors + b.engagement.unique_authors - shared_commenters;
+
+            pairs.push(VideoPair {
+                video_a: a_id.clone(),
+                video_b: b_id.clone(),
+                entity_jaccard: ratio(shared_entities.len() as f64, entity_union as f64),
+                shared_entities,
+                shared_commenters,
+                commenter_jaccard: ratio(shared_commenters as f64, commenter_union as f64)
+            });
+        }
+    }
+
+    let entities_in_all: Vec<EntityKey> = entity_sets.split_first()
+        .map(|(first, rest)| first.iter().filter(|key| rest.iter().all(|set| set.contains(key))).cloned().collect())
+        .unwrap_or_default();
+
+    Ok(VideoComparison {
+        videos,
+        pairs,
+        entities_in_all,
+        shared_commenters
+    })
+}
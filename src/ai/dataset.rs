@@ -0,0 +1,341 @@
+use std::collections::{HashMap, HashSet};
+use axum::extract::State;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use crate::db::{
+    connection::AppState,
+    operations::{CommentRepository, AnnotationEditRepository}
+};
+use crate::ai::annotation_edits::with_overrides;
+use crate::ai::ner::{build_db_json_as_annotations, AnnotationObject};
+use crate::routes::errors::AppError;
+
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DatasetFormat {
+    // {"text": ..., "entities": [[start, end, label]]} per line, with character offsets
+    #[default]
+    Spacy,
+    // One "token TAG" line per token with BIO tags, blank line between comments
+    Conll,
+    // [{"tokenized_text": [...], "ner": [[first_token, last_token, label]]}]
+    Gliner
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DatasetRequest {
+    pub video_ids: Option<Vec<String>>,
+    // Empty keeps every label
+    pub labels: Vec<String>,
+    pub verified_only: bool,
+    pub exclude_flagged: bool
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Token {
+    pub text: String,
+    pub start: usize,
+    pub end: usize
+}
+
+// Byte offsets into the comment content
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EntitySpan {
+    pub start: usize,
+    pub end: usize,
+    pub label: String
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetExample {
+    pub comment_id: String,
+    pub text: String,
+    pub tokens: Vec<Token>,
+    pub spans: Vec<EntitySpan>
+}
+
+// Runs of alphanumeric characters are words; any other non-space character is its own token
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut word_start: Option<usize> = None;
+
+    for (index, c) in text.char_indices() {
+        if c.is_alphanumeric() {
+            word_start.get_or_insert(index);
+            continue;
+        }
+        if let Some(start) = word_start.take() {
+            tokens.push(Token { text: text[start..index].to_string(), start, end: index });
+        }
+        if !c.is_whitespace() {
+            let end = index + c.len_utf8();
+            tokens.push(Token { text: text[index..end].to_string(), start: index, end });
+        }
+    }
+    if let Some(start) = word_start {
+        tokens.push(Token { text: text[start..].to_string(), start, end: text.len() });
+    }
+
+    tokens
+}
+
+fn is_word_boundary(content: &str, index: usize) -> bool {
+    let before = content[..index].chars().next_back();
+    let after = content[index..].chars().next();
+    !(before.is_some_and(|c| c.is_alphanumeric()) && after.is_some_and(|c| c.is_alphanumeric()))
+}
+
+// Stored annotations only keep the entity text, so spans are recovered by searching the content.
+// Matches ignore ASCII case and must start and end on word boundaries.
+fn find_spans(content: &str, label: &str, entity: &str) -> Vec<EntitySpan> {
+    let mut spans = Vec::new();
+    if entity.is_empty() {
+        return spans;
+    }
+
+    for (start, _) in content.char_indices() {
+        let end = start + entity.len();
+        if end > content.len() || !content.is_char_boundary(end) {
+            continue;
+        }
+        if content[start..end].eq_ignore_ascii_case(entity)
+            && is_word_boundary(content, start)
+            && is_word_boundary(content, end) {
+            spans.push(EntitySpan { start, end, label: label.to_string() });
+        }
+    }
+
+    spans
+}
+
+// Longer spans win over the shorter spans they overlap, e.g. "Elon Musk" over "Musk"
+fn resolve_overlaps(mut spans: Vec<EntitySpan>) -> Vec<EntitySpan> {
+    spans.sort_by(|a, b| (b.end - b.start).cmp(&(a.end - a.start)).then_with(|| a.start.cmp(&b.start)));
+
+    let mut kept: Vec<EntitySpan> = Vec::new();
+    for span in spans {
+        if kept.iter().all(|other| span.end <= other.start || span.start >= other.end) {
+            kept.push(span);
+        }
+    }
+
+    kept.sort_by_key(|span| span.start);
+    kept
+}
+
+fn token_range(tokens: &[Token], span: &EntitySpan) -> Option<(usize, usize)> {
+    let first = tokens.iter().position(|token| token.end > span.start)?;
+    let last = tokens.iter().rposition(|token| token.start < span.end)?;
+    (first <= last).then_some((first, last))
+}
+
+// Comments where an annotated entity can't be found in the text are left out rather than
+// exported with a missing entity, which would teach the model to skip it.
+// Verified comments take their entities from the stored overrides, so a row whose annotations
+// were reset by a refresh is never exported as verified with no entities.
+pub async fn build_dataset(dataset_request: &DatasetRequest, State(app_state): State<AppState>) -> Result<Vec<DatasetExample>, AppError> {
+    let comments = CommentRepository::get_for_dataset(
+        &app_state.db_pool,
+        dataset_request.video_ids.as_deref(),
+        dataset_request.verified_only,
+        dataset_request.exclude_flagged
+    ).await?;
+
+    let comment_ids: Vec<String> = comments.iter().map(|comment| comment.comment_id.clone()).collect();
+    let overrides = AnnotationEditRepository::get_overrides(&app_state.db_pool, &comment_ids).await?;
+    let mut verified: HashMap<String, AnnotationObject> = with_overrides(&comments, &overrides)
+        .into_iter()
+        .map(|ann_obj| (ann_obj.id.clone(), ann_obj))
+        .collect();
+
+    let labels: HashSet<String> = dataset_request.labels.iter().map(|label| label.to_lowercase()).collect();
+
+    let mut examples = Vec::new();
+    for comment in &comments {
+        let Some(ann_obj) = verified.remove(&comment.comment_id)
+            .or_else(|| build_db_json_as_annotations(std::slice::from_ref(comment)).pop()) else { continue };
+        let mut spans = Vec::new();
+        let mut located = true;
+
+        for (label, annotations) in ann_obj.annotations.iter() {
+            if !labels.is_empty() && !labels.contains(&label.to_lowercase()) {
+                continue;
+            }
+            for annotation in annotations.iter() {
+                let found = find_spans(&comment.content, label, annotation);
+                located &= !found.is_empty();
+                spans.extend(found);
+            }
+        }
+        if !located {
+            continue;
+        }
+
+        examples.push(DatasetExample {
+            comment_id: comment.comment_id.clone(),
+            text: comment.content.clone(),
+            tokens: tokenize(&comment.content),
+            spans: resolve_overlaps(spans)
+        });
+    }
+
+    Ok(examples)
+}
+
+pub fn to_spacy_jsonl(examples: &[DatasetExample]) -> String {
+    let mut jsonl = String::new();
+    for example in examples {
+        // spaCy expects character offsets, not byte offsets
+        let char_offset = |byte: usize| example.text[..byte].chars().count();
+        let entities: Vec<serde_json::Value> = example.spans.iter()
+            .map(|span| json!([char_offset(span.start), char_offset(span.end), span.label]))
+            .collect();
+
+        jsonl.push_str(&json!({
+            "id": example.comment_id,
+            "text": example.text,
+            "entities": entities
+        }).to_string());
+        jsonl.push('\n');
+    }
+    jsonl
+}
+
+pub fn to_conll(examples: &[DatasetExample]) -> String {
+    let mut conll = String::new();
+    for example in examples {
+        let mut tags = vec!["O".to_string(); example.tokens.len()];
+        for span in &example.spans {
+            let Some((first, last)) = token_range(&example.tokens, span) else { continue };
+            tags[first] = format!("B-{}", span.label);
+            for tag in &mut tags[first + 1..=last] {
+                *tag = format!("I-{}", span.label);
+            }
+        }
+
+        for (token, tag) in example.tokens.iter().zip(&tags) {
+            conll.push_str(&format!("{} {}\n", token.text, tag));
+        }
+        conll.push('\n');
+    }
+    conll
+}
+
+pub fn to_gliner_json(examples: &[DatasetExample]) -> String {
+    let records: Vec<serde_json::Value> = examples.iter()
+        .map(|example| {
+            let ner: Vec<serde_json::Value> = example.spans.iter()
+                .filter_map(|span| token_range(&example.tokens, span).map(|(first, last)| json!([first, last, span.label])))
+                .collect();
+            json!({
+                "id": example.comment_id,
+                "tokenized_text": example.tokens.iter().map(|token| token.text.as_str()).collect::<Vec<&str>>(),
+                "ner": ner
+            })
+        })
+        .collect();
+
+    serde_json::Value::Array(records).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::PgPool;
+    use crate::db::connection::insert_test_video;
+
+    const TEXT: &str = "Elon Musk met Zoë in Kraków!";
+
+    fn example(text: &str, entities: &[(&str, &str)]) -> DatasetExample {
+        let spans = entities.iter().flat_map(|(label, entity)| find_spans(text, label, entity)).collect();
+        DatasetExample {
+            comment_id: "comment".to_string(),
+            text: text.to_string(),
+            tokens: tokenize(text),
+            spans: resolve_overlaps(spans)
+        }
+    }
+
+    fn span(start: usize, end: usize, label: &str) -> EntitySpan {
+        EntitySpan { start, end, label: label.to_string() }
+    }
+
+    #[test]
+    fn tokenizes_multibyte_text_with_byte_offsets() {
+        let tokens = tokenize(TEXT);
+        let texts: Vec<&str> = tokens.iter().map(|token| token.text.as_str()).collect();
+        assert_eq!(texts, vec!["Elon", "Musk", "met", "Zoë", "in", "Kraków", "!"]);
+
+        for token in &tokens {
+            assert_eq!(&TEXT[token.start..token.end], token.text);
+        }
+        assert_eq!((tokens[3].start, tokens[3].end), (14, 18));
+        assert_eq!(tokenize("北京 欢迎你").len(), 2);
+    }
+
+    #[test]
+    fn finds_spans_on_word_boundaries_ignoring_ascii_case() {
+        assert_eq!(find_spans(TEXT, "person", "zoë"), vec![span(14, 18, "person")]);
+        assert_eq!(find_spans(TEXT, "loc", "Kraków"), vec![span(22, 29, "loc")]);
+        assert_eq!(find_spans("Musk and muskrats", "person", "Musk"), vec![span(0, 4, "person")]);
+        assert_eq!(find_spans("Zoë and Zoë", "person", "Zoë"), vec![span(0, 4, "person"), span(9, 13, "person")]);
+        assert!(find_spans(TEXT, "person", "").is_empty());
+        // "Zoe" is three bytes, which ends in the middle of "ë", so it must not panic or match
+        assert!(find_spans(TEXT, "person", "Zoe").is_empty());
+    }
+
+    #[test]
+    fn longer_spans_win_overlaps() {
+        let resolved = resolve_overlaps(vec![
+            span(5, 9, "person"),
+            span(22, 29, "loc"),
+            span(0, 9, "person"),
+            span(0, 4, "org")
+        ]);
+        assert_eq!(resolved, vec![span(0, 9, "person"), span(22, 29, "loc")]);
+    }
+
+    #[test]
+    fn writes_bio_tags_for_conll() {
+        let conll = to_conll(&[example(TEXT, &[("person", "Musk"), ("person", "Elon Musk"), ("person", "Zoë"), ("loc", "Kraków")])]);
+        assert_eq!(conll, "Elon B-person\nMusk I-person\nmet O\nZoë B-person\nin O\nKraków B-loc\n! O\n\n");
+    }
+
+    #[test]
+    fn writes_token_ranges_for_gliner() {
+        let gliner = to_gliner_json(&[example(TEXT, &[("person", "Musk"), ("person", "Elon Musk"), ("loc", "Kraków")])]);
+        let records: serde_json::Value = serde_json::from_str(&gliner).unwrap();
+        assert_eq!(records, json!([{
+            "id": "comment",
+            "tokenized_text": ["Elon", "Musk", "met", "Zoë", "in", "Kraków", "!"],
+            "ner": [[0, 1, "person"], [5, 5, "loc"]]
+        }]));
+    }
+
+    #[sqlx::test]
+    async fn verified_comments_take_entities_from_overrides(pool: PgPool) {
+        insert_test_video(&pool, "video").await;
+        // "reset" lost its annotations to a refresh; "unverified" was never corrected
+        sqlx::query(r#"
+            INSERT INTO comments (comment_id, channel_id, video_id, display_name, content, annotations) VALUES
+                ('reset', 'a', 'video', 'a', 'Elon Musk was at the Tesla launch', '{}'),
+                ('unverified', 'b', 'video', 'b', 'Tesla again', '{"org": ["Tesla"]}')
+        "#)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO annotation_overrides (comment_id, label, text, action, editor) VALUES ('reset', 'person', 'Elon Musk', 'add', 'analyst')")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let app_state = AppState::for_tests(pool);
+        let dataset_request = DatasetRequest { verified_only: true, ..Default::default() };
+        let examples = build_dataset(&dataset_request, State(app_state)).await.unwrap();
+
+        assert_eq!(examples.len(), 1);
+        assert_eq!(examples[0].comment_id, "reset");
+        assert_eq!(examples[0].spans, vec![span(0, 9, "person")]);
+    }
+}
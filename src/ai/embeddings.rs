@@ -0,0 +1,77 @@
+use axum::extract::State;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use crate::db::{
+    connection::AppState,
+    models::CommentContentAndId,
+    operations::{CommentRepository, EmbeddingRepository}
+};
+use crate::ai::endpoints::embedding_endpoint;
+use crate::routes::errors::AppError;
+
+// Comments sent to the embedding model per request
+const EMBEDDING_BATCH_SIZE: usize = 50;
+
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmbeddingRequest {
+    pub video_id: String
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingResult {
+    pub id: String,
+    pub embedding: Vec<f32>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmbeddingRequestResult {
+    #[serde(default)]
+    pub model: Option<String>,
+    pub results: Vec<EmbeddingResult>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmbeddingUpdateCounts {
+    pub video_id: String,
+    pub comments: usize,
+    pub embedded: u64
+}
+
+pub async fn request_embeddings(client: &reqwest::Client, items: &[CommentContentAndId]) -> Result<EmbeddingRequestResult, AppError> {
+    let response = client
+        .post(embedding_endpoint())
+        .json(&json!({ "comments": items }))
+        .send()
+        .await
+        .map_err(|e| AppError::AIServerError(e.to_string()))?;
+
+    response
+        .json::<EmbeddingRequestResult>()
+        .await
+        .map_err(|e| AppError::AIServerError(e.to_string()))
+}
+
+pub async fn embedding_request(embedding_request: EmbeddingRequest, State(app_state): State<AppState>) -> Result<EmbeddingUpdateCounts, AppError> {
+    let comments = CommentRepository::get_by_video_id(&app_state.db_pool, &embedding_request.video_id).await?;
+
+    let client = reqwest::Client::new();
+    let mut embedded = 0;
+
+    for batch in comments.chunks(EMBEDDING_BATCH_SIZE) {
+        let content_and_ids = CommentRepository::get_comment_content_and_ids(batch);
+        let embedding_results = request_embeddings(&client, &content_and_ids).await?;
+
+        embedded += EmbeddingRepository::upsert_batch(
+            &app_state.db_pool,
+            embedding_results.model,
+            embedding_results.results
+        ).await?;
+    }
+
+    Ok(EmbeddingUpdateCounts {
+        video_id: embedding_request.video_id,
+        comments: comments.len(),
+        embedded
+    })
+}
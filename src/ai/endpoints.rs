@@ -0,0 +1,47 @@
+// Model server URLs, configured through the environment like DATABASE_URL and PORT
+const DEFAULT_AI_SERVER_URL: &str = "http://localhost:8080";
+
+pub fn ai_server_url() -> String {
+    std::env::var("AI_SERVER_URL")
+        .unwrap_or_else(|_| DEFAULT_AI_SERVER_URL.to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
+
+pub fn ner_endpoint() -> String {
+    std::env::var("NER_ENDPOINT").unwrap_or_else(|_| format!("{}/ner", ai_server_url()))
+}
+
+pub fn sentiment_endpoint() -> String {
+    std::env::var("SENTIMENT_ENDPOINT").unwrap_or_else(|_| format!("{}/sentiment", ai_server_url()))
+}
+
+// The moderation classifier is optional; heuristic flagging runs without it
+pub fn moderation_endpoint() -> Option<String> {
+    std::env::var("MODERATION_ENDPOINT").ok().filter(|url| !url.is_empty())
+}
+
+pub fn embedding_endpoint() -> String {
+    std::env::var("EMBEDDING_ENDPOINT").unwrap_or_else(|_| format!("{}/embed", ai_server_url()))
+}
+
+// Any OpenAI-compatible chat completions endpoint, including a local stub server
+pub fn llm_endpoint() -> String {
+    std::env::var("LLM_ENDPOINT").unwrap_or_else(|_| format!("{}/v1/chat/completions", ai_server_url()))
+}
+
+pub fn llm_model() -> String {
+    std::env::var("LLM_MODEL").unwrap_or_else(|_| "gpt-4o-mini".to_string())
+}
+
+pub fn llm_api_key() -> Option<String> {
+    std::env::var("LLM_API_KEY").ok().filter(|key| !key.is_empty())
+}
+
+// Per-language NER models, e.g. NER_ENDPOINT_SPA for comments detected as Spanish
+pub fn ner_endpoint_for(language: Option<&str>) -> String {
+    language
+        .and_then(|language| std::env::var(format!("NER_ENDPOINT_{}", language.to_uppercase())).ok())
+        .filter(|url| !url.is_empty())
+        .unwrap_or_else(ner_endpoint)
+}
@@ -0,0 +1,194 @@
+use std::collections::{BTreeSet, HashMap};
+use axum::extract::State;
+use serde::{Deserialize, Serialize};
+use crate::db::{
+    connection::AppState,
+    models::Comment,
+    operations::CommentRepository
+};
+use crate::ai::ner::build_db_json_as_annotations;
+use crate::routes::errors::AppError;
+
+
+// What counts as "mentioned together": the same comment, or anywhere in the same thread
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CoOccurrenceScope {
+    #[default]
+    Comment,
+    Thread
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityNode {
+    pub id: String,
+    pub label: String,
+    pub text: String,
+    pub count: u32
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityEdge {
+    pub source: String,
+    pub target: String,
+    pub weight: u32
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct EntityGraph {
+    pub nodes: Vec<EntityNode>,
+    pub edges: Vec<EntityEdge>
+}
+
+fn node_id(label: &str, text: &str) -> String {
+    format!("{}:{}", label, text)
+}
+
+// Replies point at their top level comment through `reply_to`
+fn thread_id(comment: &Comment) -> &str {
+    match comment.reply_to.as_deref() {
+        Some(reply_to) if comment.comment_level.unwrap_or(0) > 0 && !reply_to.is_empty() => reply_to,
+        _ => comment.comment_id.as_str()
+    }
+}
+
+pub async fn build_entity_graph(
+    video_id: &str,
+    scope: &CoOccurrenceScope,
+    min_count: &u32,
+    min_weight: &u32,
+    exclude_flagged: bool,
+    State(app_state): State<AppState>
+) -> Result<EntityGraph, AppError> {
+    let comments = CommentRepository::get_by_video_id_filtered(&app_state.db_pool, video_id, exclude_flagged).await?;
+    let threads: HashMap<&str, &str> = comments.iter()
+        .map(|comment| (comment.comment_id.as_str(), thread_id(comment)))
+        .collect();
+
+    // Group entity mentions by comment or by thread before counting
+    let mut units: HashMap<String, BTreeSet<(String, String)>> = HashMap::new();
+    for ann_obj in build_db_json_as_annotations(&comments) {
+        let unit = match scope {
+            CoOccurrenceScope::Comment => ann_obj.id.clone(),
+            CoOccurrenceScope::Thread => threads.get(ann_obj.id.as_str()).copied().unwrap_or(ann_obj.id.as_str()).to_string()
+        };
+        let entities = units.entry(unit).or_default();
+        for (label, annotations) in ann_obj.annotations.iter() {
+            for annotation in annotations.iter() {
+                entities.insert((label.clone(), annotation.clone()));
+            }
+        }
+    }
+
+    let mut counts: HashMap<(String, String), u32> = HashMap::new();
+    for entities in units.values() {
+        for entity in entities {
+            *counts.entry(entity.clone()).or_insert(0) += 1;
+        }
+    }
+
+    let mut weights: HashMap<(String, String), u32> = HashMap::new();
+    for entities in units.values() {
+        let retained: Vec<String> = entities.iter()
+            .filter(|entity| counts.get(*entity).is_some_and(|count| count >= min_count))
+            .map(|(label, text)| node_id(label, text))
+            .collect();
+
+        // BTreeSet iteration is ordered, so a given pair is always keyed in the same order
+        for (i, source) in retained.iter().enumerate() {
+            for target in &retained[i + 1..] {
+                *weights.entry((source.clone(), target.clone())).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut nodes: Vec<EntityNode> = counts.into_iter()
+        .filter(|(_, count)| count >= min_count)
+        .map(|((label, text), count)| EntityNode {
+            id: node_id(&label, &text),
+            label,
+            text,
+            count
+        })
+        .collect();
+    nodes.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.id.cmp(&b.id)));
+
+    let mut edges: Vec<EntityEdge> = weights.into_iter()
+        .filter(|(_, weight)| weight >= min_weight)
+        .map(|((source, target), weight)| EntityEdge { source, target, weight })
+        .collect();
+    edges.sort_by(|a, b| b.weight.cmp(&a.weight)
+        .then_with(|| a.source.cmp(&b.source))
+        .then_with(|| a.target.cmp(&b.target)));
+
+    Ok(EntityGraph { nodes, edges })
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+impl EntityGraph {
+    pub fn to_graphml(&self) -> String {
+        let mut xml = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+            "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+            "  <key id=\"text\" for=\"node\" attr.name=\"text\" attr.type=\"string\"/>\n",
+            "  <key id=\"count\" for=\"node\" attr.name=\"count\" attr.type=\"int\"/>\n",
+            "  <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"int\"/>\n",
+            "  <graph id=\"entities\" edgedefault=\"undirected\">\n"
+        ));
+
+        for node in &self.nodes {
+            xml.push_str(&format!(
+                "    <node id=\"{}\"><data key=\"label\">{}</data><data key=\"text\">{}</data><data key=\"count\">{}</data></node>\n",
+                xml_escape(&node.id), xml_escape(&node.label), xml_escape(&node.text), node.count
+            ));
+        }
+        for (i, edge) in self.edges.iter().enumerate() {
+            xml.push_str(&format!(
+                "    <edge id=\"e{}\" source=\"{}\" target=\"{}\"><data key=\"weight\">{}</data></edge>\n",
+                i, xml_escape(&edge.source), xml_escape(&edge.target), edge.weight
+            ));
+        }
+
+        xml.push_str("  </graph>\n</graphml>\n");
+        xml
+    }
+
+    pub fn to_gexf(&self) -> String {
+        let mut xml = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<gexf xmlns=\"http://gexf.net/1.3\" version=\"1.3\">\n",
+            "  <graph mode=\"static\" defaultedgetype=\"undirected\">\n",
+            "    <attributes class=\"node\">\n",
+            "      <attribute id=\"0\" title=\"label\" type=\"string\"/>\n",
+            "      <attribute id=\"1\" title=\"count\" type=\"integer\"/>\n",
+            "    </attributes>\n",
+            "    <nodes>\n"
+        ));
+
+        for node in &self.nodes {
+            xml.push_str(&format!(
+                "      <node id=\"{}\" label=\"{}\"><attvalues><attvalue for=\"0\" value=\"{}\"/><attvalue for=\"1\" value=\"{}\"/></attvalues></node>\n",
+                xml_escape(&node.id), xml_escape(&node.text), xml_escape(&node.label), node.count
+            ));
+        }
+        xml.push_str("    </nodes>\n    <edges>\n");
+        for (i, edge) in self.edges.iter().enumerate() {
+            xml.push_str(&format!(
+                "      <edge id=\"e{}\" source=\"{}\" target=\"{}\" weight=\"{}\"/>\n",
+                i, xml_escape(&edge.source), xml_escape(&edge.target), edge.weight
+            ));
+        }
+
+        xml.push_str("    </edges>\n  </graph>\n</gexf>\n");
+        xml
+    }
+}
@@ -1,2 +1,14 @@
+pub mod endpoints;
 pub mod ner;
+pub mod ner_runs;
+pub mod entity_graph;
+pub mod sentiment;
+pub mod moderation;
+pub mod embeddings;
+pub mod topics;
+pub mod search;
+pub mod summary;
+pub mod annotation_edits;
+pub mod dataset;
+pub mod comparison;
 pub use ner::AnnotationObject;
\ No newline at end of file
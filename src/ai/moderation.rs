@@ -0,0 +1,333 @@
+use std::collections::{HashMap, HashSet};
+use axum::extract::State;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use crate::db::{
+    connection::AppState,
+    models::Comment,
+    operations::{CommentRepository, FlagRepository}
+};
+use crate::ai::endpoints::moderation_endpoint;
+use crate::routes::errors::AppError;
+
+// Comments sent to the moderation classifier per request
+const MODERATION_BATCH_SIZE: usize = 50;
+// Very short comments ("first", "lol") are too common to be treated as copy-paste spam
+const MIN_DUPLICATE_LENGTH: usize = 20;
+const MAX_LINKS: usize = 2;
+const MAX_LINK_RATIO: f32 = 0.2;
+
+// Scam signals are scored rather than matched outright, so a comment that just mentions an app or
+// a job title stays below the flag threshold while the usual "contact him on whatsapp +1..." does not
+const SCAM_FLAG_POINTS: u32 = 10;
+const SCAM_PHRASES: [(&str, u32); 14] = [
+    ("check my channel", 10),
+    ("check out my channel", 10),
+    ("visit my channel", 10),
+    ("sub to my channel", 10),
+    ("subscribe to my channel", 10),
+    ("contact him on", 10),
+    ("message him on", 10),
+    ("dm me for", 10),
+    ("crypto investment", 10),
+    ("bitcoin investment", 10),
+    ("guaranteed profit", 10),
+    ("forex trading", 4),
+    ("financial advisor", 4),
+    ("investment manager", 4)
+];
+// Messaging apps only count in full when asked to get in touch on them
+const MESSAGING_APPS: [&str; 2] = ["whatsapp", "telegram"];
+const CONTACT_WORDS: [&str; 7] = ["contact", "message", "text", "reach", "write", "dm", "inbox"];
+const CONTACT_WINDOW: usize = 5;
+const APP_MENTION_POINTS: u32 = 4;
+const APP_CONTACT_POINTS: u32 = 10;
+const CONTACT_DETAIL_POINTS: u32 = 6;
+const CONTACT_LINKS: [&str; 2] = ["t.me/", "wa.me/"];
+const MIN_PHONE_DIGITS: usize = 8;
+
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModerationRequest {
+    pub video_id: String,
+    #[serde(default)]
+    pub use_classifier: bool,
+    #[serde(default)]
+    pub classifier_threshold: Option<f32>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewCommentFlag {
+    pub comment_id: String,
+    pub source: String,
+    pub reason: String,
+    pub score: Option<f32>,
+    pub detail: Option<String>
+}
+
+impl NewCommentFlag {
+    fn heuristic(comment_id: &str, reason: &str, detail: String) -> Self {
+        NewCommentFlag {
+            comment_id: comment_id.to_string(),
+            source: "heuristic".to_string(),
+            reason: reason.to_string(),
+            score: None,
+            detail: Some(detail)
+        }
+    }
+}
+
+// Per-comment label scores from the classifier, e.g. {"toxic": 0.91, "spam": 0.12}
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModerationResult {
+    pub id: String,
+    pub labels: HashMap<String, f32>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModerationRequestResult {
+    pub results: Vec<ModerationResult>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModerationSummary {
+    pub video_id: String,
+    pub comments: usize,
+    pub flagged_comments: usize,
+    pub flags_by_reason: HashMap<String, usize>
+}
+
+fn normalize_content(content: &str) -> String {
+    content
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+fn is_link(word: &str) -> bool {
+    let word = word.to_lowercase();
+    word.starts_with("http://") || word.starts_with("https://") || word.starts_with("www.")
+}
+
+// The same text posted by more than one author
+fn flag_duplicate_content(comments: &[Comment]) -> Vec<NewCommentFlag> {
+    let mut groups: HashMap<String, Vec<&Comment>> = HashMap::new();
+    for comment in comments {
+        let normalized = normalize_content(&comment.content);
+        if normalized.len() >= MIN_DUPLICATE_LENGTH {
+            groups.entry(normalized).or_default().push(comment);
+        }
+    }
+
+    let mut flags = Vec::new();
+    for group in groups.values() {
+        let authors: HashSet<&str> = group.iter().map(|comment| comment.channel_id.as_str()).collect();
+        if authors.len() < 2 {
+            continue;
+        }
+        for comment in group {
+            flags.push(NewCommentFlag::heuristic(
+                &comment.comment_id,
+                "duplicate_content",
+                format!("Same text posted by {} authors", authors.len())
+            ));
+        }
+    }
+    flags
+}
+
+fn flag_link_density(comment: &Comment) -> Option<NewCommentFlag> {
+    let words: Vec<&str> = comment.content.split_whitespace().collect();
+    let links = words.iter().filter(|word| is_link(word)).count();
+    if links == 0 {
+        return None;
+    }
+
+    let ratio = links as f32 / words.len() as f32;
+    (links > MAX_LINKS || ratio > MAX_LINK_RATIO).then(|| NewCommentFlag::heuristic(
+        &comment.comment_id,
+        "link_density",
+        format!("{} links in {} words", links, words.len())
+    ))
+}
+
+// A run of at least MIN_PHONE_DIGITS digits, allowing the separators people type in numbers
+fn has_phone_number(content: &str) -> bool {
+    let mut digits = 0;
+    for c in content.chars() {
+        if c.is_ascii_digit() {
+            digits += 1;
+            if digits >= MIN_PHONE_DIGITS {
+                return true;
+            }
+        } else if !matches!(c, ' ' | '-' | '.' | '+' | '(' | ')') {
+            digits = 0;
+        }
+    }
+    false
+}
+
+fn scam_points(content: &str) -> (u32, Vec<&'static str>) {
+    let mut points = 0;
+    let mut matched = Vec::new();
+
+    for (phrase, weight) in SCAM_PHRASES {
+        if content.contains(phrase) {
+            points += weight;
+            matched.push(phrase);
+        }
+    }
+
+    let normalized = normalize_content(content);
+    let words: Vec<&str> = normalized.split_whitespace().collect();
+    for app in MESSAGING_APPS {
+        let positions: Vec<usize> = words.iter().enumerate()
+            .filter(|(_, word)| **word == app)
+            .map(|(index, _)| index)
+            .collect();
+        if positions.is_empty() {
+            continue;
+        }
+        let asked_to_contact = positions.iter().any(|&index| {
+            words[index.saturating_sub(CONTACT_WINDOW)..index].iter().any(|word| CONTACT_WORDS.contains(word))
+        });
+        points += if asked_to_contact { APP_CONTACT_POINTS } else { APP_MENTION_POINTS };
+        matched.push(app);
+    }
+
+    if has_phone_number(content) || CONTACT_LINKS.iter().any(|link| content.contains(link)) {
+        points += CONTACT_DETAIL_POINTS;
+        matched.push("contact details");
+    }
+
+    (points, matched)
+}
+
+fn flag_scam_phrases(comment: &Comment) -> Option<NewCommentFlag> {
+    let (points, matched) = scam_points(&comment.content.to_lowercase());
+
+    // Reported relative to the flag threshold, so every flagged comment scores at least 1.0
+    (points >= SCAM_FLAG_POINTS).then(|| NewCommentFlag {
+        score: Some(points as f32 / SCAM_FLAG_POINTS as f32),
+        ..NewCommentFlag::heuristic(
+            &comment.comment_id,
+            "scam_phrase",
+            format!("Matched: {}", matched.join(", "))
+        )
+    })
+}
+
+pub fn build_heuristic_flags(comments: &[Comment]) -> Vec<NewCommentFlag> {
+    let mut flags = flag_duplicate_content(comments);
+    for comment in comments {
+        flags.extend(flag_link_density(comment));
+        flags.extend(flag_scam_phrases(comment));
+    }
+    flags
+}
+
+async fn request_classifier_flags(
+    endpoint: &str,
+    comments: &[Comment],
+    threshold: f32
+) -> Result<Vec<NewCommentFlag>, AppError> {
+    let client = reqwest::Client::new();
+    let mut flags = Vec::new();
+
+    for batch in comments.chunks(MODERATION_BATCH_SIZE) {
+        let content_and_ids = CommentRepository::get_comment_content_and_ids(batch);
+
+        let response = client
+            .post(endpoint)
+            .json(&json!({ "comments": content_and_ids }))
+            .send()
+            .await
+            .map_err(|e| AppError::AIServerError(e.to_string()))?;
+
+        let moderation_results: ModerationRequestResult = response
+            .json::<ModerationRequestResult>()
+            .await
+            .map_err(|e| AppError::AIServerError(e.to_string()))?;
+
+        for result in moderation_results.results {
+            for (label, score) in result.labels {
+                if score >= threshold {
+                    flags.push(NewCommentFlag {
+                        comment_id: result.id.clone(),
+                        source: "classifier".to_string(),
+                        reason: label.to_lowercase(),
+                        score: Some(score),
+                        detail: None
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(flags)
+}
+
+// Re-running moderation replaces every flag previously stored for the video
+pub async fn moderation_request(moderation_request: ModerationRequest, State(app_state): State<AppState>) -> Result<ModerationSummary, AppError> {
+    let comments = CommentRepository::get_by_video_id(&app_state.db_pool, &moderation_request.video_id).await?;
+
+    let mut flags = build_heuristic_flags(&comments);
+
+    if moderation_request.use_classifier {
+        let endpoint = moderation_endpoint()
+            .ok_or_else(|| AppError::InvalidInput("MODERATION_ENDPOINT is not configured".to_string()))?;
+        let threshold = moderation_request.classifier_threshold.unwrap_or(0.8);
+        flags.extend(request_classifier_flags(&endpoint, &comments, threshold).await?);
+    }
+
+    let mut flags_by_reason: HashMap<String, usize> = HashMap::new();
+    let mut flagged_comments: HashSet<String> = HashSet::new();
+    for flag in &flags {
+        *flags_by_reason.entry(flag.reason.clone()).or_insert(0) += 1;
+        flagged_comments.insert(flag.comment_id.clone());
+    }
+
+    FlagRepository::replace_for_video(&app_state.db_pool, &moderation_request.video_id, flags).await?;
+
+    Ok(ModerationSummary {
+        video_id: moderation_request.video_id,
+        comments: comments.len(),
+        flagged_comments: flagged_comments.len(),
+        flags_by_reason
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_scam(content: &str) -> bool {
+        scam_points(&content.to_lowercase()).0 >= SCAM_FLAG_POINTS
+    }
+
+    #[test]
+    fn bare_app_mentions_are_not_flagged() {
+        assert!(!is_scam("We moved the band group chat from WhatsApp to Telegram last year"));
+        assert!(!is_scam("My financial advisor watches this channel too"));
+        assert!(!is_scam("Is the financial advisor in the video on whatsapp?"));
+    }
+
+    #[test]
+    fn contact_requests_are_flagged() {
+        assert!(is_scam("Mr. Adams changed my life, contact him on whatsapp"));
+        assert!(is_scam("Reach out to her on Telegram for the signals"));
+        assert!(is_scam("WhatsApp +1 (555) 123-4567"));
+        assert!(is_scam("Join the group t.me/signals telegram"));
+        assert!(is_scam("Guaranteed profit every week"));
+    }
+
+    #[test]
+    fn detects_phone_numbers() {
+        assert!(has_phone_number("call +44 20 7946 0958 now"));
+        assert!(!has_phone_number("the 2024 season had 38 games"));
+    }
+}
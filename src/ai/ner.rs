@@ -2,20 +2,60 @@ use reqwest;
 use std::collections::{HashMap, HashSet};
 use axum::{Json, extract::{State, Path}};
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value, Map};
+use serde_json::json;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use sqlx::PgPool;
 use crate::db::{
     connection::AppState,
-    models::{Comment},
-    operations::{CommentRepository}
+    models::{Comment, AnnotationScope, RankedEntityRow, NerPreset, AnnotationOverride},
+    operations::{CommentRepository, PresetRepository, AnnotationEditRepository}
 };
+use crate::ai::annotation_edits::AnnotationAction;
+use crate::ai::ner_runs::{NerRun, NerRunStatus};
+use uuid::Uuid;
+use crate::events::{Operation, ProgressReporter};
+use crate::webhooks::{self, WebhookEvent};
+use crate::ai::endpoints::ner_endpoint_for;
 use crate::routes::errors::AppError;
 
+// Comments sent to the AI server per request when annotating in the background
+const NER_BATCH_SIZE: usize = 50;
+
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct NERRequest {
     video_id: String,
+    #[serde(default)]
     labels: Vec<String>,
-    threshold: f32
+    #[serde(default)]
+    threshold: Option<f32>,
+    // A saved preset supplies labels and threshold when the request leaves them out
+    #[serde(default)]
+    pub preset: Option<String>,
+    #[serde(default)]
+    pub background: bool,
+    #[serde(default)]
+    pub counts_only: bool,
+    // ISO 639-3 codes to annotate, e.g. ["eng"]; empty annotates every language
+    #[serde(default)]
+    pub languages: Vec<String>,
+    // Whether `languages` also lets through comments whose language couldn't be detected,
+    // which are mostly short comments
+    #[serde(default)]
+    pub include_undetected: bool
+}
+
+impl NERRequest {
+    fn accepts_language(&self, language: Option<&str>) -> bool {
+        if self.languages.is_empty() {
+            return true;
+        }
+        match language {
+            Some(language) => self.languages.iter().any(|accepted| accepted.eq_ignore_ascii_case(language)),
+            None => self.include_undetected
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -38,6 +78,13 @@ pub struct NERRequestResult {
     pub results: Vec<NERResult>
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NERUpdateCounts {
+    pub video_id: String,
+    pub comments: usize,
+    pub updated: u64
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Annotations(HashMap<String, HashSet<String>>);
 impl Annotations {
@@ -49,6 +96,30 @@ impl Annotations {
     pub fn iter(&self) -> std::collections::hash_map::Iter<String, HashSet<String>> {
         self.0.iter()
     }
+
+    // Labels are stored lowercase so entity filters can use JSONB containment
+    pub fn insert(&mut self, label: &str, text: &str) {
+        self.0.entry(label.to_lowercase()).or_default().insert(text.to_string());
+    }
+
+    pub fn remove(&mut self, label: &str, text: &str) {
+        for (key, values) in self.0.iter_mut() {
+            if key.eq_ignore_ascii_case(label) {
+                values.remove(text);
+            }
+        }
+        self.0.retain(|_, values| !values.is_empty());
+    }
+
+    pub fn apply_overrides<'a>(&mut self, overrides: impl IntoIterator<Item = &'a AnnotationOverride>) {
+        for annotation_override in overrides {
+            match AnnotationAction::parse(&annotation_override.action) {
+                Some(AnnotationAction::Add) => self.insert(&annotation_override.label, &annotation_override.text),
+                Some(AnnotationAction::Remove) => self.remove(&annotation_override.label, &annotation_override.text),
+                None => {}
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -57,14 +128,68 @@ pub struct AnnotationObject {
     pub annotations: Annotations
 }
 
+// A single label/text pair, matched with JSONB containment so the GIN index on `annotations` is used.
+// Stored labels are lowercase, so the label is lowercased here too.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityFilter {
+    pub label: String,
+    pub text: String
+}
+
+impl EntityFilter {
+    pub fn new(label: &str, text: &str) -> Self {
+        EntityFilter {
+            label: label.trim().to_lowercase(),
+            text: text.to_string()
+        }
+    }
+
+    // Parses the `label:text` form used in query strings, e.g. `person:Elon Musk`
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        match value.split_once(':') {
+            Some((label, text)) if !label.trim().is_empty() && !text.is_empty() => Ok(EntityFilter::new(label, text)),
+            _ => Err(AppError::InvalidInput(format!("Entity filter must be in the form label:text, got '{}'", value)))
+        }
+    }
+
+    pub fn to_containment(&self) -> serde_json::Value {
+        json!({ self.label.clone(): [self.text.clone()] })
+    }
+}
+
+// How much a single comment mentioning an entity contributes to that entity's rank
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RankingMode {
+    #[default]
+    Count,
+    Likes,
+    Replies,
+    DistinctAuthors,
+    TopLevel
+}
+
+impl RankingMode {
+    // Returns None when the comment should not be counted at all. Likes and replies are
+    // offset by one so that a mention in an unengaged comment still counts.
+    fn weight(&self, comment: &Comment) -> Option<u64> {
+        match self {
+            RankingMode::Count | RankingMode::DistinctAuthors => Some(1),
+            RankingMode::Likes => Some(1 + comment.like_count.unwrap_or(0).max(0) as u64),
+            RankingMode::Replies => Some(1 + comment.reply_count.unwrap_or(0).max(0) as u64),
+            RankingMode::TopLevel => (comment.comment_level.unwrap_or(0) == 0).then_some(1)
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct RankedAnnotations(HashMap<String, HashMap<String, u32>>);
+pub struct RankedAnnotations(HashMap<String, HashMap<String, u64>>);
 
 impl RankedAnnotations {
     fn to_sorted_annotations(&self) -> SortedAnnotations{
         let mut final_sorted_annotations = SortedAnnotations::new();
         for (label, annotations) in &self.0 {
-            let mut sorted_annotations: Vec<(String, u32)> =
+            let mut sorted_annotations: Vec<(String, u64)> =
                 annotations.iter()
                     .map(|(k, v)| (k.clone(), *v))
                     .collect();
@@ -76,7 +201,7 @@ impl RankedAnnotations {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct SortedAnnotations(HashMap<String, Vec<(String, u32)>>);
+pub struct SortedAnnotations(HashMap<String, Vec<(String, u64)>>);
 
 impl SortedAnnotations{
     pub fn new() -> Self {
@@ -86,8 +211,8 @@ impl SortedAnnotations{
     pub fn filter_by_threshold(&self, threshold: &u32) -> SortedAnnotations{
         let mut filtered_annotations = SortedAnnotations::new();
         for (label, annotations) in &self.0{
-            let filtered_vec: Vec<(String, u32)> = annotations.iter()
-                .filter(|(_, count)| count >= threshold)
+            let filtered_vec: Vec<(String, u64)> = annotations.iter()
+                .filter(|(_, count)| *count >= u64::from(*threshold))
                 .cloned()
                 .collect();
             if !filtered_vec.is_empty() {
@@ -98,38 +223,236 @@ impl SortedAnnotations{
     }
 }
 
-pub async fn ner_request(ner_request: NERRequest, State(app_state): State<AppState>) -> Result<Vec<Comment>, AppError> {
-    let video_request_id = ner_request.video_id.as_str();
-    let comments = CommentRepository::get_by_video_id(&app_state.db_pool, video_request_id).await?;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VideoEntityCount {
+    pub video_id: String,
+    pub count: i64
+}
 
-    let content_and_ids = CommentRepository::get_comment_content_and_ids(&comments);
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RankedEntity {
+    pub text: String,
+    pub count: i64,
+    pub videos: Vec<VideoEntityCount>
+}
 
-    let payload = json!({
-        "comments": content_and_ids,
-        "labels": ner_request.labels,
-        "threshold": ner_request.threshold
-    });
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AggregatedRankedAnnotations(HashMap<String, Vec<RankedEntity>>);
+
+impl AggregatedRankedAnnotations {
+    // Rows arrive ordered by label and count, so entity order within each label is preserved
+    fn from_rows(rows: Vec<RankedEntityRow>) -> Self {
+        let mut aggregated = AggregatedRankedAnnotations::default();
+        for row in rows {
+            let per_video: HashMap<String, i64> = serde_json::from_value(row.videos).unwrap_or_default();
+            let mut videos: Vec<VideoEntityCount> = per_video.into_iter()
+                .map(|(video_id, count)| VideoEntityCount { video_id, count })
+                .collect();
+            videos.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.video_id.cmp(&b.video_id)));
 
-    let client = reqwest::Client::new();
-    let response = client
-        .post("http://localhost:8080/ner")
-        .json(&payload)
-        .send()
-        .await.map_err(|e| AppError::AIServerError(e.to_string()))?;
+            aggregated.0.entry(row.label).or_default().push(RankedEntity {
+                text: row.text,
+                count: row.count,
+                videos
+            });
+        }
+        aggregated
+    }
+}
 
-    let ner_results: NERRequestResult = response
-        .json::<NERRequestResult>()
-        .await
-        .map_err(|e| AppError::AIServerError(e.to_string()))?;
+async fn comments_for_request(ner_request: &NERRequest, app_state: &AppState) -> Result<Vec<Comment>, AppError> {
+    let mut comments = CommentRepository::get_by_video_id(&app_state.db_pool, &ner_request.video_id).await?;
+    comments.retain(|comment| ner_request.accepts_language(comment.language.as_deref()));
+    Ok(comments)
+}
+
+async fn resolve_preset(name: &str, app_state: &AppState) -> Result<NerPreset, AppError> {
+    PresetRepository::get_by_name(&app_state.db_pool, name).await?
+        .ok_or_else(|| AppError::InvalidInput(format!("NER preset '{}' not found", name)))
+}
+
+// Fills in labels and threshold from the request's preset, if any, and checks both ended up set
+async fn apply_preset(ner_request: &mut NERRequest, app_state: &AppState) -> Result<Option<NerPreset>, AppError> {
+    let preset = match &ner_request.preset {
+        Some(name) => Some(resolve_preset(name, app_state).await?),
+        None => None
+    };
+
+    if let Some(preset) = &preset {
+        if ner_request.labels.is_empty() {
+            ner_request.labels = preset.labels.clone();
+        }
+        ner_request.threshold = ner_request.threshold.or(Some(preset.threshold));
+    }
 
-    let merged_results = merge_db_json_and_ner_results(&comments, ner_results);
+    if ner_request.labels.is_empty() || ner_request.threshold.is_none() {
+        return Err(AppError::InvalidInput("Provide labels and threshold, or the name of a saved preset".to_string()));
+    }
 
-    let updated_comments = CommentRepository::update_annotations(&app_state.db_pool, merged_results).await?;
+    Ok(preset)
+}
+
+async fn record_preset(preset: Option<&NerPreset>, comments: &[Comment], app_state: &AppState) -> Result<(), AppError> {
+    if let Some(preset) = preset {
+        let comment_ids: Vec<String> = comments.iter().map(|comment| comment.comment_id.clone()).collect();
+        PresetRepository::record_annotations(&app_state.db_pool, preset.id, &comment_ids).await?;
+    }
+    Ok(())
+}
+
+// Marks the run finished, publishes its outcome and notifies webhooks
+async fn finish_run(app_state: &AppState, run_id: &Uuid, progress: &ProgressReporter) {
+    app_state.ner_runs.finish(run_id).await;
+    let Some(run) = app_state.ner_runs.get(run_id).await else { return };
+
+    match run.status {
+        NerRunStatus::Completed => progress.completed(run.processed, Some(run.total)),
+        NerRunStatus::Cancelled => progress.failed(run.processed, Some(run.total), "Run cancelled".to_string()),
+        _ => progress.failed(run.processed, Some(run.total), run.errors.join("; "))
+    }
+    webhooks::dispatch(app_state, WebhookEvent::AnnotationRunFinished, json!(run));
+}
+
+// A synchronous request is recorded as a run with a single batch, so it finishes like a background run
+async fn report_outcome<T>(app_state: &AppState, run: &NerRun, progress: &ProgressReporter, result: Result<T, AppError>) -> Result<T, AppError> {
+    let error = result.as_ref().err().map(|e| e.to_string());
+    app_state.ner_runs.record_batch(&run.id, run.total, error).await;
+    finish_run(app_state, &run.id, progress).await;
+    result
+}
+
+// Annotates every requested comment in one batch and stores the results with `update`.
+// Returns how many comments were sent along with whatever `update` returns.
+async fn annotate_in_one_batch<T, F, Fut>(ner_request: &mut NERRequest, app_state: &AppState, update: F) -> Result<(usize, T), AppError>
+where
+    F: FnOnce(Arc<PgPool>, Vec<AnnotationObject>) -> Fut,
+    Fut: Future<Output = Result<T, AppError>>
+{
+    let preset = apply_preset(ner_request, app_state).await?;
+    let comments = comments_for_request(ner_request, app_state).await?;
+
+    let run = app_state.ner_runs.start(&ner_request.video_id, comments.len()).await;
+    let progress = app_state.events.reporter(Operation::Ner, &ner_request.video_id, Some(run.id));
+    progress.started(Some(comments.len()));
+
+    let result = async {
+        let client = reqwest::Client::new();
+        let merged_results = request_annotations(&client, app_state, &comments, ner_request).await?;
+
+        let updated = update(app_state.db_pool.clone(), merged_results).await?;
+        record_preset(preset.as_ref(), &comments, app_state).await?;
+        Ok(updated)
+    }.await;
+    let updated = report_outcome(app_state, &run, &progress, result).await?;
+
+    Ok((comments.len(), updated))
+}
+
+pub async fn ner_request(mut ner_request: NERRequest, State(app_state): State<AppState>) -> Result<Vec<Comment>, AppError> {
+    let (_, updated_comments) = annotate_in_one_batch(&mut ner_request, &app_state, |pool, merged_results| async move {
+        CommentRepository::update_annotations(&pool, merged_results).await
+    }).await?;
 
     Ok(updated_comments)
 }
 
-pub fn merge_db_json_and_ner_results(comments: &Vec<Comment>, ner_results:NERRequestResult) -> Vec<AnnotationObject> {
+pub async fn ner_request_counts(mut ner_request: NERRequest, State(app_state): State<AppState>) -> Result<NERUpdateCounts, AppError> {
+    let (comments, updated) = annotate_in_one_batch(&mut ner_request, &app_state, |pool, merged_results| async move {
+        CommentRepository::update_annotations_count(&pool, merged_results).await
+    }).await?;
+
+    Ok(NERUpdateCounts {
+        video_id: ner_request.video_id,
+        comments,
+        updated
+    })
+}
+
+pub async fn start_ner_run(mut ner_request: NERRequest, State(app_state): State<AppState>) -> Result<NerRun, AppError> {
+    let preset = apply_preset(&mut ner_request, &app_state).await?;
+    let comments = comments_for_request(&ner_request, &app_state).await?;
+
+    let run = app_state.ner_runs.start(&ner_request.video_id, comments.len()).await;
+    let run_id = run.id;
+    let cancel_requested = run.cancel_flag();
+
+    let progress = app_state.events.reporter(Operation::Ner, &ner_request.video_id, Some(run_id));
+    progress.started(Some(comments.len()));
+
+    tokio::spawn(async move {
+        let client = reqwest::Client::new();
+        let total = comments.len();
+        let mut done = 0;
+
+        for batch in comments.chunks(NER_BATCH_SIZE) {
+            if cancel_requested.load(Ordering::SeqCst) {
+                break;
+            }
+
+            let result = match request_annotations(&client, &app_state, batch, &ner_request).await {
+                Ok(merged_results) => match CommentRepository::update_annotations_count(&app_state.db_pool, merged_results).await {
+                    Ok(_) => record_preset(preset.as_ref(), batch, &app_state).await,
+                    Err(e) => Err(e)
+                },
+                Err(e) => Err(e)
+            };
+            let error = result.err().map(|e| e.to_string());
+
+            app_state.ner_runs.record_batch(&run_id, batch.len(), error).await;
+            done += batch.len();
+            progress.progress("batch", done, Some(total));
+        }
+
+        finish_run(&app_state, &run_id, &progress).await;
+    });
+
+    Ok(run)
+}
+
+// Comments are grouped by the NER endpoint configured for their language and sent to each in turn
+async fn request_annotations(
+    client: &reqwest::Client,
+    app_state: &AppState,
+    comments: &[Comment],
+    ner_request: &NERRequest
+) -> Result<Vec<AnnotationObject>, AppError> {
+    let comment_ids: Vec<String> = comments.iter().map(|comment| comment.comment_id.clone()).collect();
+    let overrides = AnnotationEditRepository::get_overrides(&app_state.db_pool, &comment_ids).await?;
+
+    let mut routed: HashMap<String, Vec<Comment>> = HashMap::new();
+    for comment in comments {
+        routed.entry(ner_endpoint_for(comment.language.as_deref())).or_default().push(comment.clone());
+    }
+
+    let mut merged_results = Vec::new();
+    for (endpoint, routed_comments) in routed {
+        let content_and_ids = CommentRepository::get_comment_content_and_ids(&routed_comments);
+
+        let payload = json!({
+            "comments": content_and_ids,
+            "labels": ner_request.labels,
+            "threshold": ner_request.threshold
+        });
+
+        let response = client
+            .post(endpoint)
+            .json(&payload)
+            .send()
+            .await.map_err(|e| AppError::AIServerError(e.to_string()))?;
+
+        let ner_results: NERRequestResult = response
+            .json::<NERRequestResult>()
+            .await
+            .map_err(|e| AppError::AIServerError(e.to_string()))?;
+
+        merged_results.extend(merge_db_json_and_ner_results(&routed_comments, ner_results, &overrides));
+    }
+
+    Ok(merged_results)
+}
+
+// Human corrections in `overrides` are applied last, so a rerun never undoes them
+pub fn merge_db_json_and_ner_results(comments: &[Comment], ner_results:NERRequestResult, overrides: &[AnnotationOverride]) -> Vec<AnnotationObject> {
     let mut merged_annotations: Vec<AnnotationObject> = Vec::new();
 
     let ner_annotations = build_ner_results_as_annotations(ner_results);
@@ -147,6 +470,9 @@ pub fn merge_db_json_and_ner_results(comments: &Vec<Comment>, ner_results:NERReq
                 let mut db_comment_annotations = found_comment.annotations.clone();
 
                 db_comment_annotations.merge(annotation.annotations);
+                db_comment_annotations.apply_overrides(
+                    overrides.iter().filter(|annotation_override| annotation_override.comment_id == found_comment.id)
+                );
 
                 let final_annotation_object = AnnotationObject {
                     id: found_comment.id.clone(),
@@ -158,7 +484,7 @@ pub fn merge_db_json_and_ner_results(comments: &Vec<Comment>, ner_results:NERReq
     }
     merged_annotations
 }
-pub fn build_db_json_as_annotations(comments: &Vec<Comment>) -> Vec<AnnotationObject> {
+pub fn build_db_json_as_annotations(comments: &[Comment]) -> Vec<AnnotationObject> {
     let mut annotation_objects: Vec<AnnotationObject> = Vec::new();
 
     for comment in comments {
@@ -232,7 +558,7 @@ pub fn build_ner_results_as_annotations(ner_results: NERRequestResult) -> Vec<An
 
         for entity in entities {
             annotations.0
-                .entry(entity.label.clone())
+                .entry(entity.label.to_lowercase())
                 .or_insert_with(HashSet::new)
                 .insert(entity.text.clone());
         }
@@ -273,22 +599,119 @@ pub fn build_ner_results_as_annotations(ner_results: NERRequestResult) -> Vec<An
     annotation_objects
 }
 
-pub async fn build_ranked_annotations(video_id: &str, threshold: &u32, State(app_state): State<AppState>) -> Result<SortedAnnotations, AppError> {
-    let mut hash_map: HashMap<String, HashMap<String, u32>> = HashMap::default();
-
-    let comments = CommentRepository::get_by_video_id(&app_state.db_pool, video_id).await?;
+pub async fn build_ranked_annotations(
+    video_id: &str,
+    threshold: &u32,
+    mode: &RankingMode,
+    exclude_flagged: bool,
+    preset: Option<&str>,
+    State(app_state): State<AppState>
+) -> Result<SortedAnnotations, AppError> {
+    let mut hash_map: HashMap<String, HashMap<String, u64>> = HashMap::default();
+    let mut authors: HashMap<String, HashMap<String, HashSet<String>>> = HashMap::default();
+
+    let preset = match preset {
+        Some(name) => Some(resolve_preset(name, &app_state).await?),
+        None => None
+    };
+    let preset_comment_ids: Option<HashSet<String>> = match &preset {
+        Some(preset) => Some(PresetRepository::get_annotated_comment_ids(&app_state.db_pool, preset.id, video_id).await?.into_iter().collect()),
+        None => None
+    };
+
+    let comments = CommentRepository::get_by_video_id_filtered(&app_state.db_pool, video_id, exclude_flagged).await?;
+    let comments_by_id: HashMap<&str, &Comment> = comments.iter()
+        .map(|comment| (comment.comment_id.as_str(), comment))
+        .collect();
     let annotation_objects = build_db_json_as_annotations(&comments);
 
     for ann_obj in annotation_objects {
+        if preset_comment_ids.as_ref().is_some_and(|comment_ids| !comment_ids.contains(&ann_obj.id)) {
+            continue;
+        }
+        let Some(comment) = comments_by_id.get(ann_obj.id.as_str()) else { continue };
+        let Some(weight) = mode.weight(comment) else { continue };
+
         for (label, annotations) in ann_obj.annotations.iter() {
+            if preset.as_ref().is_some_and(|preset| !preset.labels.iter().any(|preset_label| preset_label.eq_ignore_ascii_case(label))) {
+                continue;
+            }
+            if *mode == RankingMode::DistinctAuthors {
+                let inner_hash = authors.entry(label.clone()).or_default();
+                for annotation in annotations.iter() {
+                    inner_hash.entry(annotation.clone()).or_default().insert(comment.channel_id.clone());
+                }
+                continue;
+            }
+
+            // Like-weighted totals for a handful of viral comments can get large, so cap rather than wrap
             let inner_hash = hash_map.entry(label.clone()).or_insert_with(HashMap::new);
             for annotation in annotations.iter() {
-                *inner_hash.entry(annotation.clone()).or_insert(0) += 1;
+                let total = inner_hash.entry(annotation.clone()).or_insert(0);
+                *total = total.saturating_add(weight);
             }
         }
     }
 
+    for (label, annotations) in authors {
+        let inner_hash = hash_map.entry(label).or_insert_with(HashMap::new);
+        for (annotation, channel_ids) in annotations {
+            inner_hash.insert(annotation, channel_ids.len() as u64);
+        }
+    }
+
     let ranked_annotation = RankedAnnotations(hash_map).to_sorted_annotations().filter_by_threshold(threshold);
     Ok(ranked_annotation)
 }
 
+pub async fn build_aggregated_ranked_annotations(
+    scope: &AnnotationScope,
+    threshold: &u32,
+    limit: &u32,
+    State(app_state): State<AppState>
+) -> Result<AggregatedRankedAnnotations, AppError> {
+    if scope.video_ids.as_ref().is_some_and(|ids| ids.is_empty()) {
+        return Err(AppError::InvalidInput("video_ids cannot be empty".to_string()));
+    }
+    if scope.is_empty() {
+        return Err(AppError::InvalidInput("Provide video_ids, a channel_id or a published time window to rank annotations over".to_string()));
+    }
+    if let Some(name) = &scope.preset {
+        resolve_preset(name, &app_state).await?;
+    }
+
+    let rows = CommentRepository::get_ranked_annotations(
+        &app_state.db_pool,
+        scope,
+        *threshold as i64,
+        *limit as i64
+    ).await?;
+
+    Ok(AggregatedRankedAnnotations::from_rows(rows))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(body: serde_json::Value) -> NERRequest {
+        serde_json::from_value(body).unwrap()
+    }
+
+    #[test]
+    fn language_lists_skip_undetected_comments_unless_asked() {
+        let english_only = request(json!({ "video_id": "video", "languages": ["eng"] }));
+        assert!(english_only.accepts_language(Some("eng")));
+        assert!(english_only.accepts_language(Some("ENG")));
+        assert!(!english_only.accepts_language(Some("deu")));
+        assert!(!english_only.accepts_language(None));
+
+        let with_undetected = request(json!({ "video_id": "video", "languages": ["eng"], "include_undetected": true }));
+        assert!(with_undetected.accepts_language(None));
+        assert!(!with_undetected.accepts_language(Some("deu")));
+
+        let every_language = request(json!({ "video_id": "video" }));
+        assert!(every_language.accepts_language(Some("deu")));
+        assert!(every_language.accepts_language(None));
+    }
+}
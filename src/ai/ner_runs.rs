@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+
+// Finished runs stay queryable for this long, and only the most recent ones are kept
+const FINISHED_RUN_TTL: Duration = Duration::hours(1);
+const MAX_FINISHED_RUNS: usize = 100;
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum NerRunStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NerRun {
+    pub id: Uuid,
+    pub video_id: String,
+    pub status: NerRunStatus,
+    pub total: usize,
+    pub processed: usize,
+    pub failed: usize,
+    pub errors: Vec<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub elapsed_ms: i64,
+    #[serde(skip)]
+    cancel_requested: Arc<AtomicBool>
+}
+
+impl NerRun {
+    pub fn is_cancel_requested(&self) -> bool {
+        self.cancel_requested.load(Ordering::SeqCst)
+    }
+
+    pub fn cancel_flag(&self) -> Arc<AtomicBool> {
+        self.cancel_requested.clone()
+    }
+
+    fn with_elapsed(&self) -> NerRun {
+        let mut run = self.clone();
+        let end = run.finished_at.unwrap_or_else(Utc::now);
+        run.elapsed_ms = (end - run.started_at).num_milliseconds();
+        run
+    }
+}
+
+// Background NER runs live in memory only; they are lost on restart.
+// Finished runs are evicted when a new run starts, so the map stays bounded.
+#[derive(Debug, Clone, Default)]
+pub struct NerRunRegistry(Arc<RwLock<HashMap<Uuid, NerRun>>>);
+
+impl NerRunRegistry {
+    pub async fn start(&self, video_id: &str, total: usize) -> NerRun {
+        let run = NerRun {
+            id: Uuid::new_v4(),
+            video_id: video_id.to_string(),
+            status: NerRunStatus::Running,
+            total,
+            processed: 0,
+            failed: 0,
+            errors: Vec::new(),
+            started_at: Utc::now(),
+            finished_at: None,
+            elapsed_ms: 0,
+            cancel_requested: Arc::new(AtomicBool::new(false))
+        };
+
+        let mut runs = self.0.write().await;
+        evict_finished(&mut runs, Utc::now());
+        runs.insert(run.id, run.clone());
+        run
+    }
+
+    pub async fn get(&self, run_id: &Uuid) -> Option<NerRun> {
+        self.0.read().await.get(run_id).map(NerRun::with_elapsed)
+    }
+
+    pub async fn record_batch(&self, run_id: &Uuid, batch_size: usize, error: Option<String>) {
+        if let Some(run) = self.0.write().await.get_mut(run_id) {
+            match error {
+                None => run.processed += batch_size,
+                Some(error) => {
+                    run.failed += batch_size;
+                    run.errors.push(error);
+                }
+            }
+        }
+    }
+
+    pub async fn finish(&self, run_id: &Uuid) {
+        if let Some(run) = self.0.write().await.get_mut(run_id) {
+            run.status = if run.is_cancel_requested() {
+                NerRunStatus::Cancelled
+            } else if run.errors.is_empty() {
+                NerRunStatus::Completed
+            } else {
+                NerRunStatus::Failed
+            };
+            run.finished_at = Some(Utc::now());
+        }
+    }
+
+    // The run stops before its next batch; a batch already sent to the AI server still completes.
+    pub async fn cancel(&self, run_id: &Uuid) -> Option<NerRun> {
+        let runs = self.0.read().await;
+        let run = runs.get(run_id)?;
+        if run.status == NerRunStatus::Running {
+            run.cancel_requested.store(true, Ordering::SeqCst);
+        }
+        Some(run.with_elapsed())
+    }
+}
+
+// Drops finished runs past their TTL, then the oldest finished runs beyond the cap
+fn evict_finished(runs: &mut HashMap<Uuid, NerRun>, now: DateTime<Utc>) {
+    runs.retain(|_, run| run.finished_at.is_none_or(|finished_at| now - finished_at < FINISHED_RUN_TTL));
+
+    let mut finished: Vec<(DateTime<Utc>, Uuid)> = runs.values()
+        .filter_map(|run| run.finished_at.map(|finished_at| (finished_at, run.id)))
+        .collect();
+    if finished.len() > MAX_FINISHED_RUNS {
+        finished.sort_unstable();
+        for (_, run_id) in &finished[..finished.len() - MAX_FINISHED_RUNS] {
+            runs.remove(run_id);
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use axum::extract::State;
+use pgvector::Vector;
+use serde::{Deserialize, Serialize};
+use crate::db::{
+    connection::AppState,
+    models::{CommentContentAndId, SemanticMatch, SearchScope},
+    operations::EmbeddingRepository
+};
+use crate::ai::embeddings::request_embeddings;
+use crate::routes::errors::AppError;
+
+const MAX_SEARCH_RESULTS: i64 = 100;
+
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SemanticSearchRequest {
+    pub query: String,
+    #[serde(default)]
+    pub video_id: Option<String>,
+    #[serde(default)]
+    pub channel_id: Option<String>,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub limit: Option<i64>,
+    #[serde(default)]
+    pub exclude_flagged: bool
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SemanticSearchResult {
+    pub query: String,
+    pub results: Vec<SemanticMatch>
+}
+
+pub async fn semantic_search(search_request: SemanticSearchRequest, State(app_state): State<AppState>) -> Result<SemanticSearchResult, AppError> {
+    if search_request.query.trim().is_empty() {
+        return Err(AppError::InvalidInput("Search query cannot be empty".to_string()));
+    }
+
+    // The query text goes through the same endpoint as comments so both land in the same vector space
+    let client = reqwest::Client::new();
+    let query_item = CommentContentAndId {
+        id: "query".to_string(),
+        comment: search_request.query.clone()
+    };
+    let embedding = request_embeddings(&client, &[query_item]).await?
+        .results
+        .into_iter()
+        .next()
+        .ok_or_else(|| AppError::AIServerError("Embedding server returned no vector for the query".to_string()))?;
+
+    let limit = search_request.limit.unwrap_or(20).clamp(1, MAX_SEARCH_RESULTS);
+
+    let scope = SearchScope {
+        video_id: search_request.video_id,
+        channel_id: search_request.channel_id,
+        label: search_request.label,
+        exclude_flagged: search_request.exclude_flagged
+    };
+    let results = EmbeddingRepository::search(&app_state.db_pool, &Vector::from(embedding.embedding), &scope, limit).await?;
+
+    Ok(SemanticSearchResult {
+        query: search_request.query,
+        results
+    })
+}
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use axum::extract::State;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use crate::db::{
+    connection::AppState,
+    models::{SentimentCount, SentimentByHour, ScoredComment},
+    operations::{CommentRepository, SentimentRepository}
+};
+use crate::ai::endpoints::sentiment_endpoint;
+use crate::routes::errors::AppError;
+
+// Comments sent to the classifier per request
+const SENTIMENT_BATCH_SIZE: usize = 50;
+
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SentimentRequest {
+    pub video_id: String
+}
+
+// One classified comment as returned by the model server. `scores` holds the per-class
+// probabilities (e.g. positive/neutral/negative) and `emotions` any emotion scores it provides.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SentimentResult {
+    pub id: String,
+    pub label: String,
+    pub score: f32,
+    #[serde(default)]
+    pub scores: HashMap<String, f32>,
+    #[serde(default)]
+    pub emotions: HashMap<String, f32>
+}
+
+impl SentimentResult {
+    // Polarity in [-1, 1]: P(positive) - P(negative) when class scores are available,
+    // otherwise the top label's score signed by the label
+    pub fn polarity(&self) -> f32 {
+        let positive = self.scores.get("positive");
+        let negative = self.scores.get("negative");
+        if positive.is_some() || negative.is_some() {
+            return positive.unwrap_or(&0.0) - negative.unwrap_or(&0.0);
+        }
+
+        match self.label.to_lowercase().as_str() {
+            "positive" => self.score,
+            "negative" => -self.score,
+            _ => 0.0
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SentimentRequestResult {
+    pub results: Vec<SentimentResult>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SentimentUpdateCounts {
+    pub video_id: String,
+    pub comments: usize,
+    pub scored: u64
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SentimentSummary {
+    pub video_id: String,
+    pub distribution: Vec<SentimentCount>,
+    pub average_by_hour: Vec<SentimentByHour>,
+    pub most_positive: Vec<ScoredComment>,
+    pub most_negative: Vec<ScoredComment>
+}
+
+pub async fn sentiment_request(sentiment_request: SentimentRequest, State(app_state): State<AppState>) -> Result<SentimentUpdateCounts, AppError> {
+    let comments = CommentRepository::get_by_video_id(&app_state.db_pool, &sentiment_request.video_id).await?;
+
+    let client = reqwest::Client::new();
+    let endpoint = sentiment_endpoint();
+    let mut scored = 0;
+
+    for batch in comments.chunks(SENTIMENT_BATCH_SIZE) {
+        let content_and_ids = CommentRepository::get_comment_content_and_ids(batch);
+
+        let response = client
+            .post(&endpoint)
+            .json(&json!({ "comments": content_and_ids }))
+            .send()
+            .await
+            .map_err(|e| AppError::AIServerError(e.to_string()))?;
+
+        let sentiment_results: SentimentRequestResult = response
+            .json::<SentimentRequestResult>()
+            .await
+            .map_err(|e| AppError::AIServerError(e.to_string()))?;
+
+        scored += SentimentRepository::upsert_batch(&app_state.db_pool, sentiment_results.results).await?;
+    }
+
+    Ok(SentimentUpdateCounts {
+        video_id: sentiment_request.video_id,
+        comments: comments.len(),
+        scored
+    })
+}
+
+pub async fn build_sentiment_summary(video_id: &str, limit: &i64, exclude_flagged: bool, State(app_state): State<AppState>) -> Result<SentimentSummary, AppError> {
+    let pool = &app_state.db_pool;
+
+    Ok(SentimentSummary {
+        video_id: video_id.to_string(),
+        distribution: SentimentRepository::get_distribution(pool, video_id, exclude_flagged).await?,
+        average_by_hour: SentimentRepository::get_average_by_hour(pool, video_id, exclude_flagged).await?,
+        most_positive: SentimentRepository::get_top_level_by_polarity(pool, video_id, 1.0, *limit, exclude_flagged).await?,
+        most_negative: SentimentRepository::get_top_level_by_polarity(pool, video_id, -1.0, *limit, exclude_flagged).await?
+    })
+}
@@ -0,0 +1,334 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use axum::extract::State;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use crate::db::{
+    connection::AppState,
+    models::{Comment, VideoSummary},
+    operations::{CommentRepository, SummaryRepository, VideoInfoRepository}
+};
+use crate::ai::endpoints::{llm_api_key, llm_endpoint, llm_model};
+use crate::ai::topics::build_topics;
+use crate::routes::errors::AppError;
+
+// Bump whenever the prompt text or comment selection changes so stored summaries stay comparable
+pub const PROMPT_VERSION: &str = "v1";
+
+const DEFAULT_TOKEN_BUDGET: usize = 2000;
+const MIN_TOKEN_BUDGET: usize = 200;
+const MAX_TOKEN_BUDGET: usize = 16000;
+// Long comments are cut so a single wall of text can't use up the budget
+const MAX_COMMENT_CHARS: usize = 600;
+const CLUSTER_REPRESENTATIVES: usize = 3;
+
+const SYSTEM_PROMPT: &str = "You summarise the comment section of a YouTube video. \
+Describe the main opinions, recurring topics and notable disagreements in a few short paragraphs. \
+Only use what the comments say and do not quote commenters by name.";
+
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SummaryRequest {
+    pub token_budget: Option<usize>,
+    #[serde(default)]
+    pub exclude_flagged: bool
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatCompletionRequest {
+    pub model: String,
+    pub messages: Vec<ChatMessage>,
+    pub temperature: f32
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatChoice {
+    pub message: ChatMessage
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatCompletionResponse {
+    #[serde(default)]
+    pub model: Option<String>,
+    pub choices: Vec<ChatChoice>
+}
+
+// What the LLM call produced, before it's stored
+#[derive(Debug)]
+pub struct GeneratedSummary {
+    pub model: Option<String>,
+    pub summary: String,
+    pub source_comment_ids: Vec<String>
+}
+
+// Rough count for budgeting; close enough for English text with BPE tokenizers
+fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+fn comment_line(comment: &Comment) -> String {
+    let content: String = comment.content.split_whitespace().collect::<Vec<&str>>().join(" ");
+    let content = match content.char_indices().nth(MAX_COMMENT_CHARS) {
+        Some((index, _)) => format!("{}...", &content[..index]),
+        None => content
+    };
+    format!("- ({} likes) {}", comment.like_count.unwrap_or(0), content)
+}
+
+// Takes comments from each source in turn (top liked, one per topic cluster, most recent),
+// skipping duplicates and any comment that would go over the budget
+fn select_comments<'a>(sources: &[Vec<&'a Comment>], token_budget: usize) -> Vec<&'a Comment> {
+    let mut selected = Vec::new();
+    let mut seen: HashSet<&str> = HashSet::new();
+    let mut cursors = vec![0; sources.len()];
+    let mut used = 0;
+
+    while sources.iter().zip(&cursors).any(|(source, cursor)| *cursor < source.len()) {
+        for (source, cursor) in sources.iter().zip(cursors.iter_mut()) {
+            while let Some(comment) = source.get(*cursor) {
+                *cursor += 1;
+                if !seen.insert(comment.comment_id.as_str()) {
+                    continue;
+                }
+                let cost = estimate_tokens(&comment_line(comment));
+                if used + cost <= token_budget {
+                    used += cost;
+                    selected.push(*comment);
+                }
+                break;
+            }
+        }
+    }
+
+    selected
+}
+
+fn build_messages(title: &str, comments: &[&Comment]) -> Vec<ChatMessage> {
+    let lines: Vec<String> = comments.iter().map(|comment| comment_line(comment)).collect();
+
+    vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: SYSTEM_PROMPT.to_string()
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: format!("Video title: {}\n\nComments:\n{}", title, lines.join("\n"))
+        }
+    ]
+}
+
+async fn request_completion(endpoint: &str, messages: Vec<ChatMessage>) -> Result<(Option<String>, String), AppError> {
+    let client = reqwest::Client::new();
+    let mut request = client
+        .post(endpoint)
+        .json(&ChatCompletionRequest {
+            model: llm_model(),
+            messages,
+            temperature: 0.2
+        });
+    if let Some(api_key) = llm_api_key() {
+        request = request.bearer_auth(api_key);
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| AppError::AIServerError(e.to_string()))?
+        .error_for_status()
+        .map_err(|e| AppError::AIServerError(e.to_string()))?;
+
+    let completion: ChatCompletionResponse = response
+        .json::<ChatCompletionResponse>()
+        .await
+        .map_err(|e| AppError::AIServerError(e.to_string()))?;
+
+    let content = completion.choices
+        .into_iter()
+        .next()
+        .map(|choice| choice.message.content.trim().to_string())
+        .filter(|content| !content.is_empty())
+        .ok_or_else(|| AppError::AIServerError("LLM returned an empty completion".to_string()))?;
+
+    Ok((completion.model, content))
+}
+
+async fn generate_summary(endpoint: &str, title: &str, sources: &[Vec<&Comment>], token_budget: usize) -> Result<GeneratedSummary, AppError> {
+    let selected = select_comments(sources, token_budget);
+    let (model, summary) = request_completion(endpoint, build_messages(title, &selected)).await?;
+
+    Ok(GeneratedSummary {
+        model,
+        summary,
+        source_comment_ids: selected.iter().map(|comment| comment.comment_id.clone()).collect()
+    })
+}
+
+async fn store_summary(pool: &PgPool, video_id: &str, generated: GeneratedSummary, token_budget: usize) -> Result<VideoSummary, AppError> {
+    SummaryRepository::create(
+        pool,
+        video_id,
+        generated.model.or_else(|| Some(llm_model())),
+        PROMPT_VERSION,
+        &generated.summary,
+        &generated.source_comment_ids,
+        token_budget as i32
+    ).await
+}
+
+pub async fn summarize_video(video_id: &str, summary_request: &SummaryRequest, State(app_state): State<AppState>) -> Result<VideoSummary, AppError> {
+    let video = VideoInfoRepository::get_by_yt_id(&app_state.db_pool, video_id).await?
+        .ok_or_else(|| AppError::InvalidInput("Video not found".to_string()))?;
+
+    let comments = CommentRepository::get_by_video_id_filtered(&app_state.db_pool, video_id, summary_request.exclude_flagged).await?;
+    if comments.is_empty() {
+        return Err(AppError::InvalidInput(format!("No comments stored for video {}", video_id)));
+    }
+    let comments_by_id: HashMap<&str, &Comment> = comments.iter()
+        .map(|comment| (comment.comment_id.as_str(), comment))
+        .collect();
+
+    let mut top_liked: Vec<&Comment> = comments.iter().collect();
+    top_liked.sort_by_key(|comment| Reverse(comment.like_count.unwrap_or(0)));
+
+    let mut recent: Vec<&Comment> = comments.iter().collect();
+    recent.sort_by_key(|comment| Reverse(comment.published_at));
+
+    // Cluster representatives are only available once embeddings have been computed for the video
+    let clustered: Vec<&Comment> = match build_topics(video_id, None, &CLUSTER_REPRESENTATIVES, summary_request.exclude_flagged, State(app_state.clone())).await {
+        Ok(topics) => (0..CLUSTER_REPRESENTATIVES)
+            .flat_map(|rank| topics.topics.iter().filter_map(move |topic| topic.representative_comments.get(rank)))
+            .filter_map(|representative| comments_by_id.get(representative.comment_id.as_str()).copied())
+            .collect(),
+        Err(AppError::InvalidInput(_)) => Vec::new(),
+        Err(e) => return Err(e)
+    };
+
+    let token_budget = summary_request.token_budget
+        .unwrap_or(DEFAULT_TOKEN_BUDGET)
+        .clamp(MIN_TOKEN_BUDGET, MAX_TOKEN_BUDGET);
+    let generated = generate_summary(&llm_endpoint(), &video.title, &[top_liked, clustered, recent], token_budget).await?;
+
+    store_summary(&app_state.db_pool, video_id, generated, token_budget).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use axum::{Json, Router, routing::post};
+    use tokio::net::TcpListener;
+    use crate::db::connection::insert_test_video;
+
+    type ReceivedRequests = Arc<Mutex<Vec<ChatCompletionRequest>>>;
+
+    fn comment(comment_id: &str, like_count: i32) -> Comment {
+        Comment {
+            id: None,
+            comment_id: comment_id.to_string(),
+            channel_id: format!("channel-{}", comment_id),
+            video_id: "video".to_string(),
+            display_name: comment_id.to_string(),
+            user_verified: None,
+            thumbnail: None,
+            // Every comment costs the same number of tokens, so the budget maps to a comment count
+            content: format!("comment text for {:-<10}", comment_id),
+            published_time: None,
+            published_at: None,
+            published_precision: None,
+            published_edited: false,
+            language: None,
+            language_confidence: None,
+            like_count: Some(like_count),
+            reply_count: None,
+            comment_level: Some(0),
+            reply_to: None,
+            reply_order: None,
+            annotations: None,
+            created_at: None,
+            updated_at: None
+        }
+    }
+
+    // Answers like an OpenAI-compatible server and keeps every request it receives
+    async fn start_stub_llm() -> (String, ReceivedRequests) {
+        let received: ReceivedRequests = Arc::default();
+        let app = Router::new()
+            .route("/v1/chat/completions", post(|State(received): State<ReceivedRequests>, Json(request): Json<ChatCompletionRequest>| async move {
+                received.lock().unwrap().push(request);
+                Json(ChatCompletionResponse {
+                    model: Some("stub-model".to_string()),
+                    choices: vec![ChatChoice {
+                        message: ChatMessage { role: "assistant".to_string(), content: " Viewers liked the video. ".to_string() }
+                    }]
+                })
+            }))
+            .with_state(received.clone());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        (format!("http://{}/v1/chat/completions", address), received)
+    }
+
+    #[tokio::test]
+    async fn selects_from_each_source_within_the_token_budget() {
+        let (endpoint, received) = start_stub_llm().await;
+
+        let comments: Vec<Comment> = ["liked-1", "liked-2", "liked-3", "cluster-1", "cluster-2", "recent-1", "recent-2"]
+            .into_iter()
+            .map(|comment_id| comment(comment_id, 10))
+            .collect();
+        let by_id = |comment_id: &str| comments.iter().find(|comment| comment.comment_id == comment_id).unwrap();
+        let top_liked = vec![by_id("liked-1"), by_id("liked-2"), by_id("liked-3")];
+        let clustered = vec![by_id("cluster-1"), by_id("cluster-2")];
+        // The most liked comment is also the most recent one, and must only be sent once
+        let recent = vec![by_id("liked-1"), by_id("recent-1"), by_id("recent-2")];
+
+        let cost = estimate_tokens(&comment_line(&comments[0]));
+        let token_budget = cost * 4 + cost / 2;
+
+        let generated = generate_summary(&endpoint, "A video", &[top_liked, clustered, recent], token_budget).await.unwrap();
+
+        assert_eq!(generated.source_comment_ids, vec!["liked-1", "cluster-1", "recent-1", "liked-2"]);
+        assert_eq!(generated.model.as_deref(), Some("stub-model"));
+        assert_eq!(generated.summary, "Viewers liked the video.");
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        let prompt = &received[0].messages[1].content;
+        let sent_tokens: usize = prompt.lines()
+            .filter(|line| line.starts_with("- "))
+            .map(estimate_tokens)
+            .sum();
+        assert!(sent_tokens <= token_budget, "{} tokens sent over a budget of {}", sent_tokens, token_budget);
+        for comment_id in &generated.source_comment_ids {
+            assert_eq!(prompt.matches(&format!("for {:-<10}", comment_id)).count(), 1, "{}", comment_id);
+        }
+        assert!(!prompt.contains("cluster-2") && !prompt.contains("liked-3"));
+    }
+
+    #[sqlx::test]
+    async fn stores_prompt_version_and_source_comments(pool: PgPool) {
+        insert_test_video(&pool, "video").await;
+        let (endpoint, _) = start_stub_llm().await;
+
+        let comments = [comment("liked-1", 50), comment("recent-1", 0)];
+        let sources = [vec![&comments[0]], vec![&comments[1]]];
+        let generated = generate_summary(&endpoint, "A video", &sources, DEFAULT_TOKEN_BUDGET).await.unwrap();
+        store_summary(&pool, "video", generated, DEFAULT_TOKEN_BUDGET).await.unwrap();
+
+        let stored = SummaryRepository::get_latest_by_video_id(&pool, "video").await.unwrap().unwrap();
+        assert_eq!(stored.prompt_version, PROMPT_VERSION);
+        assert_eq!(stored.source_comment_ids, vec!["liked-1", "recent-1"]);
+        assert_eq!(stored.model.as_deref(), Some("stub-model"));
+        assert_eq!(stored.token_budget, DEFAULT_TOKEN_BUDGET as i32);
+    }
+}
@@ -0,0 +1,205 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use axum::extract::State;
+use serde::{Deserialize, Serialize};
+use crate::db::{
+    connection::AppState,
+    models::Comment,
+    operations::{CommentRepository, EmbeddingRepository}
+};
+use crate::ai::ner::build_db_json_as_annotations;
+use crate::routes::errors::AppError;
+
+const MAX_KMEANS_ITERATIONS: usize = 50;
+const MAX_DEFAULT_CLUSTERS: usize = 12;
+const TOP_ENTITIES_PER_TOPIC: usize = 5;
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopicComment {
+    pub comment_id: String,
+    pub display_name: String,
+    pub content: String,
+    pub like_count: Option<i32>,
+    pub similarity: f32
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopicEntity {
+    pub label: String,
+    pub text: String,
+    pub count: u32
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Topic {
+    pub id: usize,
+    pub size: usize,
+    pub representative_comments: Vec<TopicComment>,
+    pub top_entities: Vec<TopicEntity>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Topics {
+    pub video_id: String,
+    pub embedded_comments: usize,
+    pub topics: Vec<Topic>
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+// Vectors are normalized up front, so the dot product is the cosine similarity
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn nearest_centroid(vector: &[f32], centroids: &[Vec<f32>]) -> (usize, f32) {
+    centroids.iter()
+        .enumerate()
+        .map(|(i, centroid)| (i, dot(vector, centroid)))
+        .fold((0, f32::MIN), |best, current| if current.1 > best.1 { current } else { best })
+}
+
+// Spherical k-means with farthest-first initialisation, which keeps results deterministic
+fn kmeans(vectors: &[Vec<f32>], k: usize) -> (Vec<usize>, Vec<Vec<f32>>) {
+    let mut centroids = vec![vectors[0].clone()];
+    while centroids.len() < k {
+        let farthest = vectors.iter()
+            .map(|vector| nearest_centroid(vector, &centroids).1)
+            .enumerate()
+            .fold((0, f32::MAX), |best, current| if current.1 < best.1 { current } else { best })
+            .0;
+        centroids.push(vectors[farthest].clone());
+    }
+
+    let mut assignments = vec![usize::MAX; vectors.len()];
+    for _ in 0..MAX_KMEANS_ITERATIONS {
+        let next: Vec<usize> = vectors.iter()
+            .map(|vector| nearest_centroid(vector, &centroids).0)
+            .collect();
+        if next == assignments {
+            break;
+        }
+        assignments = next;
+
+        for (cluster, centroid) in centroids.iter_mut().enumerate() {
+            let mut sum = vec![0.0; centroid.len()];
+            let mut members = 0;
+            for (vector, _) in vectors.iter().zip(&assignments).filter(|(_, assigned)| **assigned == cluster) {
+                sum.iter_mut().zip(vector).for_each(|(s, v)| *s += v);
+                members += 1;
+            }
+            // An empty cluster keeps its previous centroid
+            if members > 0 {
+                normalize(&mut sum);
+                *centroid = sum;
+            }
+        }
+    }
+
+    (assignments, centroids)
+}
+
+fn default_cluster_count(comments: usize) -> usize {
+    ((comments as f64 / 2.0).sqrt().round() as usize).clamp(2, MAX_DEFAULT_CLUSTERS)
+}
+
+fn top_entities(members: &[Comment]) -> Vec<TopicEntity> {
+    let mut counts: HashMap<(String, String), u32> = HashMap::new();
+    for ann_obj in build_db_json_as_annotations(members) {
+        for (label, annotations) in ann_obj.annotations.iter() {
+            for annotation in annotations.iter() {
+                *counts.entry((label.clone(), annotation.clone())).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut entities: Vec<TopicEntity> = counts.into_iter()
+        .map(|((label, text), count)| TopicEntity { label, text, count })
+        .collect();
+    entities.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.text.cmp(&b.text)));
+    entities.truncate(TOP_ENTITIES_PER_TOPIC);
+    entities
+}
+
+pub async fn build_topics(
+    video_id: &str,
+    clusters: Option<usize>,
+    representatives: &usize,
+    exclude_flagged: bool,
+    State(app_state): State<AppState>
+) -> Result<Topics, AppError> {
+    let comments = CommentRepository::get_by_video_id_filtered(&app_state.db_pool, video_id, exclude_flagged).await?;
+    let comments_by_id: HashMap<&str, &Comment> = comments.iter()
+        .map(|comment| (comment.comment_id.as_str(), comment))
+        .collect();
+
+    let embeddings: Vec<_> = EmbeddingRepository::get_by_video_id(&app_state.db_pool, video_id).await?
+        .into_iter()
+        .filter(|embedding| comments_by_id.contains_key(embedding.comment_id.as_str()))
+        .collect();
+    if embeddings.is_empty() {
+        return Err(AppError::InvalidInput(format!("No comment embeddings stored for video {}", video_id)));
+    }
+
+    let vectors: Vec<Vec<f32>> = embeddings.iter()
+        .map(|embedding| {
+            let mut vector = embedding.embedding.to_vec();
+            normalize(&mut vector);
+            vector
+        })
+        .collect();
+
+    let k = clusters.unwrap_or_else(|| default_cluster_count(vectors.len())).clamp(1, vectors.len());
+    let (assignments, centroids) = kmeans(&vectors, k);
+
+    let mut topics: Vec<Topic> = Vec::new();
+    for (cluster, centroid) in centroids.iter().enumerate() {
+        let mut members: Vec<(&Comment, f32)> = embeddings.iter()
+            .zip(&vectors)
+            .zip(&assignments)
+            .filter(|(_, assigned)| **assigned == cluster)
+            .filter_map(|((embedding, vector), _)| {
+                comments_by_id.get(embedding.comment_id.as_str()).map(|comment| (*comment, dot(vector, centroid)))
+            })
+            .collect();
+        if members.is_empty() {
+            continue;
+        }
+        members.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        let member_comments: Vec<Comment> = members.iter().map(|(comment, _)| (*comment).clone()).collect();
+
+        topics.push(Topic {
+            id: 0,
+            size: members.len(),
+            representative_comments: members.iter()
+                .take(*representatives)
+                .map(|(comment, similarity)| TopicComment {
+                    comment_id: comment.comment_id.clone(),
+                    display_name: comment.display_name.clone(),
+                    content: comment.content.clone(),
+                    like_count: comment.like_count,
+                    similarity: *similarity
+                })
+                .collect(),
+            top_entities: top_entities(&member_comments)
+        });
+    }
+
+    topics.sort_by_key(|topic| Reverse(topic.size));
+    for (id, topic) in topics.iter_mut().enumerate() {
+        topic.id = id;
+    }
+
+    Ok(Topics {
+        video_id: video_id.to_string(),
+        embedded_comments: embeddings.len(),
+        topics
+    })
+}
@@ -1,12 +1,16 @@
 use sqlx::{PgPool};
 use std::{sync::Arc};
 
+use crate::ai::ner_runs::NerRunRegistry;
+use crate::events::EventBus;
 use crate::routes::errors::AppError;
 
 
 #[derive(Clone)]
 pub struct AppState {
     pub db_pool: Arc<PgPool>,
+    pub ner_runs: NerRunRegistry,
+    pub events: EventBus,
 }
 
 pub async fn get_connection() -> Result<AppState, AppError>  {
@@ -18,8 +22,33 @@ pub async fn get_connection() -> Result<AppState, AppError>  {
         .map_err(|e| AppError::FailedDBConnection(e.to_string()))?;
 
     let state = AppState {
-        db_pool: Arc::new(db_pool)
+        db_pool: Arc::new(db_pool),
+        ner_runs: NerRunRegistry::default(),
+        events: EventBus::default()
     };
 
     Ok(state)
-}
\ No newline at end of file
+}
+
+// `#[sqlx::test]` hands each test a scratch database with every migration applied, so the test
+// server needs pgvector, as in docker-compose.yml
+#[cfg(test)]
+impl AppState {
+    pub fn for_tests(pool: PgPool) -> Self {
+        AppState {
+            db_pool: Arc::new(pool),
+            ner_runs: NerRunRegistry::default(),
+            events: EventBus::default()
+        }
+    }
+}
+
+// A bare `video_info` row for test comments to reference
+#[cfg(test)]
+pub async fn insert_test_video(pool: &PgPool, yt_id: &str) {
+    sqlx::query("INSERT INTO video_info (yt_id, title, channel, channel_id) VALUES ($1, 'A video', 'A channel', 'channel')")
+        .bind(yt_id)
+        .execute(pool)
+        .await
+        .unwrap();
+}
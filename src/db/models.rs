@@ -1,6 +1,7 @@
 use serde::{Serialize, Deserialize};
 use sqlx::FromRow;
 use chrono::{DateTime, Utc};
+use pgvector::Vector;
 
 use crate::ai::ner::Annotations;
 
@@ -17,6 +18,8 @@ pub struct VideoInfo {
     pub like_count: i64,
     pub video_thumbnail: Option<String>,
     pub upload_date: Option<String>,
+    pub uploaded_at: Option<DateTime<Utc>>,
+    pub upload_date_precision: Option<String>,
     pub channel_thumbnail: Option<String>,
     pub created_at: Option<DateTime<Utc>>,
     pub updated_at: Option<DateTime<Utc>>,
@@ -33,6 +36,11 @@ pub struct Comment {
     pub thumbnail: Option<String>,
     pub content: String,
     pub published_time: Option<String>,
+    pub published_at: Option<DateTime<Utc>>,
+    pub published_precision: Option<String>,
+    pub published_edited: bool,
+    pub language: Option<String>,
+    pub language_confidence: Option<f32>,
     pub like_count: Option<i32>,
     pub reply_count: Option<i32>,
     pub comment_level: Option<i32>,
@@ -56,6 +64,8 @@ pub struct CreateVideoInfoDto {
     pub like_count: u64,
     pub video_thumbnail: String,
     pub upload_date: String,
+    pub uploaded_at: Option<DateTime<Utc>>,
+    pub upload_date_precision: Option<String>,
     pub channel_thumbnail: String,
 }
 
@@ -69,6 +79,11 @@ pub struct CreateCommentDto {
     pub thumbnail: String,
     pub content: String,
     pub published_time: String,
+    pub published_at: Option<DateTime<Utc>>,
+    pub published_precision: Option<String>,
+    pub published_edited: bool,
+    pub language: Option<String>,
+    pub language_confidence: Option<f32>,
     pub like_count: i32,
     pub reply_count: i32,
     pub comment_level: i32,
@@ -80,4 +95,330 @@ pub struct CreateCommentDto {
 pub struct CommentContentAndId {
     pub id: String,
     pub comment: String
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Pagination {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>
+}
+
+impl Pagination {
+    const DEFAULT_LIMIT: i64 = 100;
+    const MAX_LIMIT: i64 = 1000;
+
+    pub fn limit(&self) -> i64 {
+        self.limit.unwrap_or(Self::DEFAULT_LIMIT).clamp(1, Self::MAX_LIMIT)
+    }
+
+    pub fn offset(&self) -> i64 {
+        self.offset.unwrap_or(0).max(0)
+    }
+}
+
+// Which comments an aggregated annotation ranking is computed over
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnnotationScope {
+    pub video_ids: Option<Vec<String>>,
+    pub channel_id: Option<String>,
+    // The time window bounds `published_at`, parsed from `published_time` at ingestion.
+    // Comments whose publish time couldn't be parsed fall outside any window.
+    pub published_after: Option<DateTime<Utc>>,
+    pub published_before: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub exclude_flagged: bool,
+    // Only count comments annotated with this preset, and only the preset's labels
+    #[serde(default)]
+    pub preset: Option<String>
+}
+
+impl AnnotationScope {
+    pub fn is_empty(&self) -> bool {
+        self.video_ids.is_none()
+            && self.channel_id.is_none()
+            && self.published_after.is_none()
+            && self.published_before.is_none()
+    }
+}
+
+// Which comments a semantic search ranks
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchScope {
+    pub video_id: Option<String>,
+    pub channel_id: Option<String>,
+    // Matched against annotation labels, ignoring case
+    pub label: Option<String>,
+    #[serde(default)]
+    pub exclude_flagged: bool
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RankedEntityRow {
+    pub label: String,
+    pub text: String,
+    pub count: i64,
+    pub videos: serde_json::Value
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TimelineBucket {
+    pub bucket: DateTime<Utc>,
+    pub count: i64
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SentimentCount {
+    pub label: String,
+    pub count: i64
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SentimentByHour {
+    pub hour: DateTime<Utc>,
+    pub average_polarity: f64,
+    pub count: i64
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ScoredComment {
+    pub comment_id: String,
+    pub display_name: String,
+    pub content: String,
+    pub like_count: Option<i32>,
+    pub published_time: Option<String>,
+    pub label: String,
+    pub polarity: f32
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct FlaggedComment {
+    pub comment_id: String,
+    pub display_name: String,
+    pub content: String,
+    pub source: String,
+    pub reason: String,
+    pub score: Option<f32>,
+    pub detail: Option<String>,
+    pub created_at: Option<DateTime<Utc>>
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct CommentEmbedding {
+    pub comment_id: String,
+    pub embedding: Vector
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SemanticMatch {
+    pub comment_id: String,
+    pub video_id: String,
+    pub display_name: String,
+    pub content: String,
+    pub like_count: Option<i32>,
+    pub published_time: Option<String>,
+    pub annotations: Option<serde_json::Value>,
+    pub similarity: f64
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct VideoSummary {
+    pub id: i32,
+    pub video_id: String,
+    pub model: Option<String>,
+    pub prompt_version: String,
+    pub summary: String,
+    pub source_comment_ids: Vec<String>,
+    pub token_budget: i32,
+    pub created_at: Option<DateTime<Utc>>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct NerPreset {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub labels: Vec<String>,
+    pub threshold: f32,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateNerPresetDto {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub labels: Vec<String>,
+    pub threshold: f32
+}
+
+// The current human correction for one entity on one comment; `action` is "add" or "remove"
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AnnotationOverride {
+    pub comment_id: String,
+    pub label: String,
+    pub text: String,
+    pub action: String,
+    pub editor: String,
+    pub updated_at: Option<DateTime<Utc>>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AnnotationEdit {
+    pub id: i32,
+    pub comment_id: String,
+    pub label: String,
+    pub text: String,
+    pub action: String,
+    pub editor: String,
+    pub note: Option<String>,
+    pub created_at: Option<DateTime<Utc>>
+}
+
+// `secret` is only returned when the webhook is created
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Webhook {
+    pub id: i32,
+    pub url: String,
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub events: Vec<String>,
+    pub comment_count_threshold: i32,
+    pub active: bool,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateWebhookDto {
+    pub url: String,
+    // Generated when not provided
+    #[serde(default)]
+    pub secret: Option<String>,
+    #[serde(default)]
+    pub events: Vec<String>,
+    #[serde(default)]
+    pub comment_count_threshold: Option<i32>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WebhookDelivery {
+    pub id: i32,
+    pub webhook_id: i32,
+    pub event: String,
+    pub payload: serde_json::Value,
+    pub status: String,
+    pub attempts: i32,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub replay_of: Option<i32>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub delivered_at: Option<DateTime<Utc>>
+}
+
+// `first_seen_at` and `last_seen_at` come from comment publish times, falling back to when they were ingested
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Author {
+    pub channel_id: String,
+    pub display_name: String,
+    pub thumbnail: Option<String>,
+    pub user_verified: Option<bool>,
+    pub first_seen_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AuthorStats {
+    pub comment_count: i64,
+    pub video_count: i64,
+    pub total_likes: i64,
+    pub total_replies: i64
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AuthorVideo {
+    pub video_id: String,
+    pub title: Option<String>,
+    pub comment_count: i64,
+    pub total_likes: i64,
+    pub last_comment_at: Option<DateTime<Utc>>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AuthorEntity {
+    pub label: String,
+    pub text: String,
+    pub count: i64
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CommenterStats {
+    pub channel_id: String,
+    pub display_name: String,
+    pub user_verified: bool,
+    pub comment_count: i64,
+    pub total_likes: i64,
+    // YouTube's reply counts summed over all of the author's comments
+    pub replies_received: i64,
+    pub threads_started: i64,
+    // Scraped replies under the author's top-level comments
+    pub thread_replies: i64,
+    pub thread_repliers: i64
+}
+
+// Percentiles are over `like_count`; all figures are for the comments currently stored
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct VideoCommentStats {
+    pub scraped_comments: i64,
+    pub top_level_comments: i64,
+    pub replies: i64,
+    pub unique_authors: i64,
+    pub verified_authors: i64,
+    pub creator_comments: i64,
+    pub creator_replies: i64,
+    pub average_length: Option<f64>,
+    pub total_likes: i64,
+    pub likes_p25: Option<f64>,
+    pub likes_p50: Option<f64>,
+    pub likes_p75: Option<f64>,
+    pub likes_p90: Option<f64>,
+    pub likes_p99: Option<f64>,
+    pub max_likes: Option<i32>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DepthCount {
+    pub depth: i32,
+    pub count: i64
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RepliedThread {
+    pub comment_id: String,
+    pub channel_id: String,
+    pub display_name: String,
+    pub content: String,
+    pub like_count: Option<i32>,
+    // YouTube's count, which can be higher than the replies actually scraped
+    pub reply_count: Option<i32>,
+    pub scraped_replies: i64
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SharedCommenter {
+    pub channel_id: String,
+    pub display_name: String,
+    pub video_count: i64,
+    pub comment_count: i64,
+    // `{video_id: comment_count}`
+    pub videos: serde_json::Value
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SharedCommenterPair {
+    pub video_a: String,
+    pub video_b: String,
+    pub shared: i64
+}
@@ -1,7 +1,19 @@
+use std::collections::HashMap;
 use sqlx::PgPool;
-use crate::db::models::{VideoInfo, Comment, CreateVideoInfoDto, CreateCommentDto, CommentContentAndId};
+use chrono::{DateTime, Utc};
+use crate::db::models::{VideoInfo, Comment, CreateVideoInfoDto, CreateCommentDto, CommentContentAndId, Pagination, AnnotationScope, RankedEntityRow, TimelineBucket,
+                        SentimentCount, SentimentByHour, ScoredComment, FlaggedComment, CommentEmbedding, SemanticMatch, SearchScope, VideoSummary,
+                        NerPreset, CreateNerPresetDto, AnnotationOverride, AnnotationEdit, Webhook, CreateWebhookDto, WebhookDelivery,
+                        Author, AuthorStats, AuthorVideo, AuthorEntity, CommenterStats,
+                        VideoCommentStats, DepthCount, RepliedThread, SharedCommenter, SharedCommenterPair};
 use crate::routes::errors::AppError;
-use crate::ai::ner::AnnotationObject;
+use crate::ai::ner::{AnnotationObject, EntityFilter};
+use crate::ai::sentiment::SentimentResult;
+use crate::ai::moderation::NewCommentFlag;
+use crate::ai::embeddings::EmbeddingResult;
+use crate::ai::annotation_edits::AnnotationChange;
+use pgvector::Vector;
+use futures_util::{stream::BoxStream, StreamExt, TryStreamExt};
 use serde_json::json;
 
 pub struct VideoInfoRepository;
@@ -12,8 +24,9 @@ impl VideoInfoRepository {
             VideoInfo,
             r#"
             INSERT INTO video_info 
-            (title, channel, channel_id, description, yt_id, views, comment_count, like_count, video_thumbnail, upload_date, channel_thumbnail)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            (title, channel, channel_id, description, yt_id, views, comment_count, like_count, video_thumbnail, upload_date, channel_thumbnail,
+             uploaded_at, upload_date_precision)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
             RETURNING *
             "#,
             video_dto.title,
@@ -26,7 +39,9 @@ impl VideoInfoRepository {
             video_dto.like_count as i64,
             Some(video_dto.video_thumbnail),
             Some(video_dto.upload_date),
-            Some(video_dto.channel_thumbnail)
+            Some(video_dto.channel_thumbnail),
+            video_dto.uploaded_at,
+            video_dto.upload_date_precision
         )
         .fetch_one(pool)
         .await
@@ -99,8 +114,9 @@ impl VideoInfoRepository {
             VideoInfo,
             r#"
             INSERT INTO video_info 
-            (title, channel, channel_id, description, yt_id, views, comment_count, like_count, video_thumbnail, upload_date, channel_thumbnail)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            (title, channel, channel_id, description, yt_id, views, comment_count, like_count, video_thumbnail, upload_date, channel_thumbnail,
+             uploaded_at, upload_date_precision)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
             RETURNING *
             "#,
             video_dto.title,
@@ -113,7 +129,9 @@ impl VideoInfoRepository {
             video_dto.like_count as i64,
             Some(video_dto.video_thumbnail),
             Some(video_dto.upload_date),
-            Some(video_dto.channel_thumbnail)
+            Some(video_dto.channel_thumbnail),
+            video_dto.uploaded_at,
+            video_dto.upload_date_precision
         )
         .fetch_one(&mut *tx)
         .await
@@ -127,10 +145,10 @@ impl VideoInfoRepository {
                 r#"
                 INSERT INTO comments
                 (comment_id, channel_id, video_id, display_name, user_verified, thumbnail, content,
-                 published_time, like_count, reply_count, comment_level, reply_to, reply_order, annotations)
-                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+                 published_time, like_count, reply_count, comment_level, reply_to, reply_order, annotations, published_at, published_precision, published_edited, language, language_confidence)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19)
                 RETURNING id, comment_id, channel_id, video_id, display_name, user_verified, thumbnail, content,
-                          published_time, like_count, reply_count, comment_level, reply_to, reply_order,
+                          published_time, published_at, published_precision, published_edited, language, language_confidence, like_count, reply_count, comment_level, reply_to, reply_order,
                           annotations, created_at, updated_at
                 "#,
                 comment_dto.comment_id,
@@ -147,6 +165,11 @@ impl VideoInfoRepository {
                 Some(comment_dto.reply_to),
                 Some(comment_dto.reply_order),
                 Some(comment_dto.annotations),
+                comment_dto.published_at,
+                comment_dto.published_precision,
+                comment_dto.published_edited,
+                comment_dto.language,
+                comment_dto.language_confidence,
             )
             .fetch_one(&mut *tx)
             .await
@@ -166,43 +189,74 @@ pub struct CommentRepository;
 impl CommentRepository {
 
     pub async fn update_annotations(pool: &PgPool, annotations: Vec<AnnotationObject>) -> Result<Vec<Comment>, AppError> {
-        let mut updated_comments: Vec<Comment> = Vec::new();
+        let (comment_ids, json_annotations) = Self::annotation_arrays(annotations);
 
-        for annotation in annotations {
-            let json_annotations: serde_json::Value = json!(annotation.annotations);
+        let mut tx = pool.begin().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
-            let comment = sqlx::query_as!(
-                Comment,
-                r#"
-                UPDATE comments
-                SET annotations = $1, updated_at = CURRENT_TIMESTAMP
-                WHERE comment_id = $2
-                RETURNING
-                    comment_id, channel_id, video_id, display_name, user_verified, thumbnail, content,
-                    published_time, like_count, reply_count, comment_level, reply_to, reply_order,
-                    annotations, created_at, updated_at, id
-                "#,
-                json_annotations,
-                annotation.id
-            ).fetch_one(pool)
-                .await
-                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let updated_comments = sqlx::query_as!(
+            Comment,
+            r#"
+            UPDATE comments AS c
+            SET annotations = u.annotations, updated_at = CURRENT_TIMESTAMP
+            FROM UNNEST($1::text[], $2::jsonb[]) AS u(comment_id, annotations)
+            WHERE c.comment_id = u.comment_id
+            RETURNING
+                c.comment_id, c.channel_id, c.video_id, c.display_name, c.user_verified, c.thumbnail, c.content,
+                c.published_time, c.published_at, c.published_precision, c.published_edited, c.language, c.language_confidence, c.like_count, c.reply_count, c.comment_level, c.reply_to, c.reply_order,
+                c.annotations, c.created_at, c.updated_at, c.id
+            "#,
+            &comment_ids,
+            &json_annotations
+        )
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
-            updated_comments.push(comment);
-        }
+        tx.commit().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
         Ok(updated_comments)
     }
+
+    pub async fn update_annotations_count(pool: &PgPool, annotations: Vec<AnnotationObject>) -> Result<u64, AppError> {
+        let (comment_ids, json_annotations) = Self::annotation_arrays(annotations);
+
+        let mut tx = pool.begin().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE comments AS c
+            SET annotations = u.annotations, updated_at = CURRENT_TIMESTAMP
+            FROM UNNEST($1::text[], $2::jsonb[]) AS u(comment_id, annotations)
+            WHERE c.comment_id = u.comment_id
+            "#,
+            &comment_ids,
+            &json_annotations
+        )
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        tx.commit().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+
+    fn annotation_arrays(annotations: Vec<AnnotationObject>) -> (Vec<String>, Vec<serde_json::Value>) {
+        annotations.into_iter()
+            .map(|annotation| (annotation.id, json!(annotation.annotations)))
+            .unzip()
+    }
+
     pub async fn create(pool: &PgPool, comment_dto: CreateCommentDto) -> Result<Comment, AppError> {
         let comment = sqlx::query_as!(
             Comment,
             r#"
             INSERT INTO comments
             (comment_id, channel_id, video_id, display_name, user_verified, thumbnail, content,
-             published_time, like_count, reply_count, comment_level, reply_to, reply_order, annotations)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+             published_time, like_count, reply_count, comment_level, reply_to, reply_order, annotations, published_at, published_precision, published_edited, language, language_confidence)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19)
             RETURNING id, comment_id, channel_id, video_id, display_name, user_verified, thumbnail, content,
-                      published_time, like_count, reply_count, comment_level, reply_to, reply_order,
+                      published_time, published_at, published_precision, published_edited, language, language_confidence, like_count, reply_count, comment_level, reply_to, reply_order,
                       annotations, created_at, updated_at
             "#,
             comment_dto.comment_id,
@@ -218,7 +272,12 @@ impl CommentRepository {
             Some(comment_dto.comment_level),
             Some(comment_dto.reply_to),
             Some(comment_dto.reply_order),
-            Some(comment_dto.annotations)
+            Some(comment_dto.annotations),
+            comment_dto.published_at,
+            comment_dto.published_precision,
+            comment_dto.published_edited,
+            comment_dto.language,
+            comment_dto.language_confidence
         )
         .fetch_one(pool)
         .await
@@ -243,7 +302,7 @@ impl CommentRepository {
             Comment,
             r#"
             SELECT comment_id, channel_id, video_id, display_name, user_verified, thumbnail, content,
-                   published_time, like_count, reply_count, comment_level, reply_to, reply_order, annotations, created_at, updated_at, id
+                   published_time, published_at, published_precision, published_edited, language, language_confidence, like_count, reply_count, comment_level, reply_to, reply_order, annotations, created_at, updated_at, id
             FROM comments
             WHERE video_id = $1
             ORDER BY comment_level ASC, reply_order ASC, published_time ASC
@@ -257,12 +316,367 @@ impl CommentRepository {
         Ok(comments)
     }
 
+    pub async fn get_by_video_id_filtered(pool: &PgPool, video_id: &str, exclude_flagged: bool) -> Result<Vec<Comment>, AppError> {
+        if exclude_flagged {
+            Self::get_unflagged_by_video_id(pool, video_id).await
+        } else {
+            Self::get_by_video_id(pool, video_id).await
+        }
+    }
+
+    pub async fn get_unflagged_by_video_id(pool: &PgPool, video_id: &str) -> Result<Vec<Comment>, AppError> {
+        let comments = sqlx::query_as!(
+            Comment,
+            r#"
+            SELECT comment_id, channel_id, video_id, display_name, user_verified, thumbnail, content,
+                   published_time, published_at, published_precision, published_edited, language, language_confidence, like_count, reply_count, comment_level, reply_to, reply_order, annotations, created_at, updated_at, id
+            FROM comments
+            WHERE video_id = $1 AND NOT EXISTS (SELECT 1 FROM comment_flags f WHERE f.comment_id = comments.comment_id)
+            ORDER BY comment_level ASC, reply_order ASC, published_time ASC
+            "#,
+            video_id
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(comments)
+    }
+
+    // Streaming counterpart of `get_by_video_id_filtered`. `language` is matched against the detected
+    // ISO 639-3 code, so comments without one never match.
+    pub fn stream_by_video_id<'a>(
+        pool: &'a PgPool,
+        video_id: &'a str,
+        language: Option<&'a str>,
+        exclude_flagged: bool
+    ) -> BoxStream<'a, Result<Comment, AppError>> {
+        sqlx::query_as!(
+            Comment,
+            r#"
+            SELECT comment_id, channel_id, video_id, display_name, user_verified, thumbnail, content,
+                   published_time, published_at, published_precision, published_edited, language, language_confidence, like_count, reply_count, comment_level, reply_to, reply_order, annotations, created_at, updated_at, id
+            FROM comments
+            WHERE video_id = $1
+              AND ($2::text IS NULL OR language = lower($2))
+              AND ($3::bool IS FALSE OR NOT EXISTS (SELECT 1 FROM comment_flags f WHERE f.comment_id = comments.comment_id))
+            ORDER BY comment_level ASC, reply_order ASC, published_time ASC
+            "#,
+            video_id,
+            language,
+            exclude_flagged
+        )
+            .fetch(pool)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))
+            .boxed()
+    }
+
+    pub async fn get_by_video_id_and_annotation(
+        pool: &PgPool,
+        video_id: &str,
+        filter: &EntityFilter,
+        pagination: &Pagination,
+        exclude_flagged: bool,
+        language: Option<&str>
+    ) -> Result<(Vec<Comment>, i64), AppError> {
+        let containment = filter.to_containment();
+        let comments = sqlx::query_as!(
+            Comment,
+            r#"
+            SELECT comment_id, channel_id, video_id, display_name, user_verified, thumbnail, content,
+                   published_time, published_at, published_precision, published_edited, language, language_confidence, like_count, reply_count, comment_level, reply_to, reply_order, annotations, created_at, updated_at, id
+            FROM comments
+            WHERE video_id = $1
+              AND annotations @> $2
+              AND ($5::bool IS FALSE OR NOT EXISTS (SELECT 1 FROM comment_flags f WHERE f.comment_id = comments.comment_id))
+              AND ($6::text IS NULL OR language = lower($6))
+            ORDER BY comment_level ASC, reply_order ASC, published_time ASC, id ASC
+            LIMIT $3 OFFSET $4
+            "#,
+            video_id,
+            containment,
+            pagination.limit(),
+            pagination.offset(),
+            exclude_flagged,
+            language
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!" FROM comments
+            WHERE video_id = $1
+              AND annotations @> $2
+              AND ($3::bool IS FALSE OR NOT EXISTS (SELECT 1 FROM comment_flags f WHERE f.comment_id = comments.comment_id))
+              AND ($4::text IS NULL OR language = lower($4))
+            "#,
+            video_id,
+            containment,
+            exclude_flagged,
+            language
+        )
+            .fetch_one(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok((comments, total))
+    }
+
+    pub async fn get_by_annotation(
+        pool: &PgPool,
+        filter: &EntityFilter,
+        pagination: &Pagination,
+        exclude_flagged: bool,
+        language: Option<&str>
+    ) -> Result<(Vec<Comment>, i64), AppError> {
+        let containment = filter.to_containment();
+        let comments = sqlx::query_as!(
+            Comment,
+            r#"
+            SELECT comment_id, channel_id, video_id, display_name, user_verified, thumbnail, content,
+                   published_time, published_at, published_precision, published_edited, language, language_confidence, like_count, reply_count, comment_level, reply_to, reply_order, annotations, created_at, updated_at, id
+            FROM comments
+            WHERE annotations @> $1
+              AND ($4::bool IS FALSE OR NOT EXISTS (SELECT 1 FROM comment_flags f WHERE f.comment_id = comments.comment_id))
+              AND ($5::text IS NULL OR language = lower($5))
+            ORDER BY video_id ASC, comment_level ASC, reply_order ASC, id ASC
+            LIMIT $2 OFFSET $3
+            "#,
+            containment,
+            pagination.limit(),
+            pagination.offset(),
+            exclude_flagged,
+            language
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!" FROM comments
+            WHERE annotations @> $1
+              AND ($2::bool IS FALSE OR NOT EXISTS (SELECT 1 FROM comment_flags f WHERE f.comment_id = comments.comment_id))
+              AND ($3::text IS NULL OR language = lower($3))
+            "#,
+            containment,
+            exclude_flagged,
+            language
+        )
+            .fetch_one(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok((comments, total))
+    }
+
+    // Newest first, across every ingested video
+    pub async fn get_by_channel_id(pool: &PgPool, channel_id: &str, pagination: &Pagination) -> Result<Vec<Comment>, AppError> {
+        let comments = sqlx::query_as!(
+            Comment,
+            r#"
+            SELECT comment_id, channel_id, video_id, display_name, user_verified, thumbnail, content,
+                   published_time, published_at, published_precision, published_edited, language, language_confidence, like_count, reply_count, comment_level, reply_to, reply_order, annotations, created_at, updated_at, id
+            FROM comments
+            WHERE channel_id = $1
+            ORDER BY published_at DESC NULLS LAST, id DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            channel_id,
+            pagination.limit(),
+            pagination.offset()
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(comments)
+    }
+
+    // Comments with machine annotations or human corrections; `verified_only` keeps just the corrected ones
+    pub async fn get_for_dataset(
+        pool: &PgPool,
+        video_ids: Option<&[String]>,
+        verified_only: bool,
+        exclude_flagged: bool
+    ) -> Result<Vec<Comment>, AppError> {
+        let comments = sqlx::query_as!(
+            Comment,
+            r#"
+            SELECT comment_id, channel_id, video_id, display_name, user_verified, thumbnail, content,
+                   published_time, published_at, published_precision, published_edited, language, language_confidence, like_count, reply_count, comment_level, reply_to, reply_order, annotations, created_at, updated_at, id
+            FROM comments
+            WHERE ($1::text[] IS NULL OR video_id = ANY($1))
+              AND (annotations <> '{}'::jsonb OR EXISTS (SELECT 1 FROM annotation_overrides o WHERE o.comment_id = comments.comment_id))
+              AND ($2::bool IS FALSE OR EXISTS (SELECT 1 FROM annotation_overrides o WHERE o.comment_id = comments.comment_id))
+              AND ($3::bool IS FALSE OR NOT EXISTS (SELECT 1 FROM comment_flags f WHERE f.comment_id = comments.comment_id))
+            ORDER BY video_id ASC, id ASC
+            "#,
+            video_ids,
+            verified_only,
+            exclude_flagged
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(comments)
+    }
+
+    // Lowercased, like the keys `build_db_json_as_annotations` produces
+    pub async fn get_annotation_labels(pool: &PgPool, video_ids: Option<&[String]>) -> Result<Vec<String>, AppError> {
+        let labels = sqlx::query_scalar!(
+            r#"
+            SELECT DISTINCT lower(label) AS "label!"
+            FROM comments,
+                 jsonb_object_keys(CASE WHEN jsonb_typeof(annotations) = 'object' THEN annotations ELSE '{}'::jsonb END) AS label
+            WHERE ($1::text[] IS NULL OR video_id = ANY($1))
+            ORDER BY 1
+            "#,
+            video_ids
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(labels)
+    }
+
+    // Rows are yielded as Postgres returns them so exports never hold the whole result set
+    pub fn stream_for_export<'a>(
+        pool: &'a PgPool,
+        video_ids: Option<&'a [String]>,
+        exclude_flagged: bool
+    ) -> BoxStream<'a, Result<Comment, AppError>> {
+        sqlx::query_as!(
+            Comment,
+            r#"
+            SELECT comment_id, channel_id, video_id, display_name, user_verified, thumbnail, content,
+                   published_time, published_at, published_precision, published_edited, language, language_confidence, like_count, reply_count, comment_level, reply_to, reply_order, annotations, created_at, updated_at, id
+            FROM comments
+            WHERE ($1::text[] IS NULL OR video_id = ANY($1))
+              AND ($2::bool IS FALSE OR NOT EXISTS (SELECT 1 FROM comment_flags f WHERE f.comment_id = comments.comment_id))
+            ORDER BY video_id ASC, id ASC
+            "#,
+            video_ids,
+            exclude_flagged
+        )
+            .fetch(pool)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))
+            .boxed()
+    }
+
+    // Counts each (label, text) pair once per comment, like `build_ranked_annotations`, but in SQL.
+    // `videos` holds the per-video breakdown as a `{video_id: count}` object.
+    pub async fn get_ranked_annotations(
+        pool: &PgPool,
+        scope: &AnnotationScope,
+        threshold: i64,
+        limit: i64
+    ) -> Result<Vec<RankedEntityRow>, AppError> {
+        let rows = sqlx::query_as!(
+            RankedEntityRow,
+            r#"
+            WITH mentions AS (
+                SELECT DISTINCT c.comment_id, c.video_id, lower(a.key) AS label, e.text
+                FROM comments c
+                JOIN video_info v ON v.yt_id = c.video_id
+                CROSS JOIN LATERAL jsonb_each(
+                    CASE WHEN jsonb_typeof(c.annotations) = 'object' THEN c.annotations ELSE '{}'::jsonb END
+                ) AS a(key, value)
+                CROSS JOIN LATERAL jsonb_array_elements_text(
+                    CASE WHEN jsonb_typeof(a.value) = 'array' THEN a.value ELSE jsonb_build_array(a.value) END
+                ) AS e(text)
+                WHERE ($1::text[] IS NULL OR c.video_id = ANY($1))
+                  AND ($2::text IS NULL OR v.channel_id = $2)
+                  AND ($5::timestamptz IS NULL OR c.published_at >= $5)
+                  AND ($6::timestamptz IS NULL OR c.published_at < $6)
+                  AND ($7::bool IS FALSE OR NOT EXISTS (SELECT 1 FROM comment_flags f WHERE f.comment_id = c.comment_id))
+                  AND ($8::text IS NULL OR EXISTS (
+                      SELECT 1 FROM comment_annotation_presets cap
+                      JOIN ner_presets p ON p.id = cap.preset_id
+                      WHERE cap.comment_id = c.comment_id AND p.name = $8
+                        AND lower(a.key) IN (SELECT lower(l) FROM unnest(p.labels) AS l)
+                  ))
+                  AND e.text <> ''
+            ),
+            per_video AS (
+                SELECT label, text, video_id, COUNT(*) AS count
+                FROM mentions
+                GROUP BY label, text, video_id
+            ),
+            totals AS (
+                SELECT label, text, SUM(count)::bigint AS count, jsonb_object_agg(video_id, count) AS videos
+                FROM per_video
+                GROUP BY label, text
+                HAVING SUM(count) >= $3::bigint
+            ),
+            ranked AS (
+                SELECT label, text, count, videos,
+                       ROW_NUMBER() OVER (PARTITION BY label ORDER BY count DESC, text ASC) AS rank
+                FROM totals
+            )
+            SELECT label as "label!", text as "text!", count as "count!", videos as "videos!"
+            FROM ranked
+            WHERE rank <= $4::bigint
+            ORDER BY label ASC, count DESC, text ASC
+            "#,
+            scope.video_ids.as_deref(),
+            scope.channel_id.as_deref(),
+            threshold,
+            limit,
+            scope.published_after,
+            scope.published_before,
+            scope.exclude_flagged,
+            scope.preset.as_deref()
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(rows)
+    }
+
+    // `bucket` must be a valid `date_trunc` unit; comments without a parsed `published_at` are skipped
+    pub async fn get_annotation_timeline(
+        pool: &PgPool,
+        video_id: &str,
+        filter: &EntityFilter,
+        bucket: &str,
+        exclude_flagged: bool
+    ) -> Result<Vec<TimelineBucket>, AppError> {
+        let containment = filter.to_containment();
+        let buckets = sqlx::query_as!(
+            TimelineBucket,
+            r#"
+            SELECT date_trunc($3, published_at) as "bucket!", COUNT(*) as "count!"
+            FROM comments
+            WHERE video_id = $1 AND published_at IS NOT NULL
+              AND annotations @> $2
+              AND ($4::bool IS FALSE OR NOT EXISTS (SELECT 1 FROM comment_flags f WHERE f.comment_id = comments.comment_id))
+            GROUP BY 1
+            ORDER BY 1 ASC
+            "#,
+            video_id,
+            containment,
+            bucket,
+            exclude_flagged
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(buckets)
+    }
+
     pub async fn get_by_comment_id(pool: &PgPool, comment_id: &str) -> Result<Option<Comment>, AppError> {
         let comment = sqlx::query_as!(
         Comment,
         r#"
         SELECT comment_id, channel_id, video_id, display_name, user_verified, thumbnail, content,
-               published_time, like_count, reply_count, comment_level, reply_to, reply_order, annotations, created_at, updated_at, id
+               published_time, published_at, published_precision, published_edited, language, language_confidence, like_count, reply_count, comment_level, reply_to, reply_order, annotations, created_at, updated_at, id
         FROM comments
         WHERE comment_id = $1
         "#,
@@ -287,7 +701,7 @@ impl CommentRepository {
         Ok(result.rows_affected())
     }
 
-    pub fn get_comment_content_and_ids(comments: &Vec<Comment>) -> Vec<CommentContentAndId> {
+    pub fn get_comment_content_and_ids(comments: &[Comment]) -> Vec<CommentContentAndId> {
         let mut comments_with_ids: Vec<CommentContentAndId> = Vec::new();
 
         for comment in comments {
@@ -299,4 +713,1197 @@ impl CommentRepository {
             }
         comments_with_ids
     }
-}
\ No newline at end of file
+}
+
+pub struct SentimentRepository;
+
+impl SentimentRepository {
+    pub async fn upsert_batch(pool: &PgPool, results: Vec<SentimentResult>) -> Result<u64, AppError> {
+        let mut comment_ids = Vec::with_capacity(results.len());
+        let mut labels = Vec::with_capacity(results.len());
+        let mut polarities = Vec::with_capacity(results.len());
+        let mut scores = Vec::with_capacity(results.len());
+        let mut emotions = Vec::with_capacity(results.len());
+
+        for result in results {
+            polarities.push(result.polarity());
+            comment_ids.push(result.id);
+            labels.push(result.label.to_lowercase());
+            scores.push(json!(result.scores));
+            emotions.push(json!(result.emotions));
+        }
+
+        let mut tx = pool.begin().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO comment_sentiment (comment_id, label, polarity, scores, emotions)
+            SELECT u.comment_id, u.label, u.polarity, u.scores, u.emotions
+            FROM UNNEST($1::text[], $2::text[], $3::real[], $4::jsonb[], $5::jsonb[])
+                AS u(comment_id, label, polarity, scores, emotions)
+            JOIN comments c ON c.comment_id = u.comment_id
+            ON CONFLICT (comment_id) DO UPDATE
+            SET label = EXCLUDED.label, polarity = EXCLUDED.polarity, scores = EXCLUDED.scores,
+                emotions = EXCLUDED.emotions, updated_at = CURRENT_TIMESTAMP
+            "#,
+            &comment_ids,
+            &labels,
+            &polarities,
+            &scores,
+            &emotions
+        )
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        tx.commit().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get_distribution(pool: &PgPool, video_id: &str, exclude_flagged: bool) -> Result<Vec<SentimentCount>, AppError> {
+        let distribution = sqlx::query_as!(
+            SentimentCount,
+            r#"
+            SELECT s.label, COUNT(*) as "count!"
+            FROM comment_sentiment s
+            JOIN comments c ON c.comment_id = s.comment_id
+            WHERE c.video_id = $1
+              AND ($2::bool IS FALSE OR NOT EXISTS (SELECT 1 FROM comment_flags f WHERE f.comment_id = c.comment_id))
+            GROUP BY s.label
+            ORDER BY 2 DESC
+            "#,
+            video_id,
+            exclude_flagged
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(distribution)
+    }
+
+    // Only comments whose publish time is known to the hour are bucketed; "3 weeks ago" would put
+    // every comment with that string into the same artificial hour
+    pub async fn get_average_by_hour(pool: &PgPool, video_id: &str, exclude_flagged: bool) -> Result<Vec<SentimentByHour>, AppError> {
+        let hours = sqlx::query_as!(
+            SentimentByHour,
+            r#"
+            SELECT date_trunc('hour', c.published_at) as "hour!",
+                   AVG(s.polarity)::float8 as "average_polarity!",
+                   COUNT(*) as "count!"
+            FROM comment_sentiment s
+            JOIN comments c ON c.comment_id = s.comment_id
+            WHERE c.video_id = $1 AND c.published_at IS NOT NULL
+              AND c.published_precision IN ('second', 'minute', 'hour')
+              AND ($2::bool IS FALSE OR NOT EXISTS (SELECT 1 FROM comment_flags f WHERE f.comment_id = c.comment_id))
+            GROUP BY 1
+            ORDER BY 1 ASC
+            "#,
+            video_id,
+            exclude_flagged
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(hours)
+    }
+
+    // `direction` is 1.0 for the most positive comments first and -1.0 for the most negative
+    pub async fn get_top_level_by_polarity(pool: &PgPool, video_id: &str, direction: f32, limit: i64, exclude_flagged: bool) -> Result<Vec<ScoredComment>, AppError> {
+        let comments = sqlx::query_as!(
+            ScoredComment,
+            r#"
+            SELECT c.comment_id, c.display_name, c.content, c.like_count, c.published_time, s.label, s.polarity
+            FROM comment_sentiment s
+            JOIN comments c ON c.comment_id = s.comment_id
+            WHERE c.video_id = $1 AND COALESCE(c.comment_level, 0) = 0
+              AND ($4::bool IS FALSE OR NOT EXISTS (SELECT 1 FROM comment_flags f WHERE f.comment_id = c.comment_id))
+            ORDER BY s.polarity * $2 DESC, c.like_count DESC NULLS LAST
+            LIMIT $3
+            "#,
+            video_id,
+            direction,
+            limit,
+            exclude_flagged
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(comments)
+    }
+}
+
+pub struct FlagRepository;
+
+impl FlagRepository {
+    pub async fn replace_for_video(pool: &PgPool, video_id: &str, flags: Vec<NewCommentFlag>) -> Result<u64, AppError> {
+        let mut comment_ids = Vec::with_capacity(flags.len());
+        let mut sources = Vec::with_capacity(flags.len());
+        let mut reasons = Vec::with_capacity(flags.len());
+        let mut scores = Vec::with_capacity(flags.len());
+        let mut details = Vec::with_capacity(flags.len());
+
+        for flag in flags {
+            comment_ids.push(flag.comment_id);
+            sources.push(flag.source);
+            reasons.push(flag.reason);
+            scores.push(flag.score);
+            details.push(flag.detail);
+        }
+
+        let mut tx = pool.begin().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM comment_flags
+            WHERE comment_id IN (SELECT comment_id FROM comments WHERE video_id = $1)
+            "#,
+            video_id
+        )
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO comment_flags (comment_id, source, reason, score, detail)
+            SELECT u.comment_id, u.source, u.reason, u.score, u.detail
+            FROM UNNEST($1::text[], $2::text[], $3::text[], $4::real[], $5::text[])
+                AS u(comment_id, source, reason, score, detail)
+            JOIN comments c ON c.comment_id = u.comment_id
+            ON CONFLICT (comment_id, source, reason) DO NOTHING
+            "#,
+            &comment_ids,
+            &sources,
+            &reasons,
+            &scores as &[Option<f32>],
+            &details as &[Option<String>]
+        )
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        tx.commit().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get_by_video_id(pool: &PgPool, video_id: &str) -> Result<Vec<FlaggedComment>, AppError> {
+        let flags = sqlx::query_as!(
+            FlaggedComment,
+            r#"
+            SELECT f.comment_id, c.display_name, c.content, f.source, f.reason, f.score, f.detail, f.created_at
+            FROM comment_flags f
+            JOIN comments c ON c.comment_id = f.comment_id
+            WHERE c.video_id = $1
+            ORDER BY f.comment_id ASC, f.source ASC, f.reason ASC
+            "#,
+            video_id
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(flags)
+    }
+}
+
+// The compile-time query macros can't type pgvector columns, so these queries are checked at runtime
+pub struct EmbeddingRepository;
+
+impl EmbeddingRepository {
+    pub async fn upsert_batch(pool: &PgPool, model: Option<String>, results: Vec<EmbeddingResult>) -> Result<u64, AppError> {
+        let (comment_ids, embeddings): (Vec<String>, Vec<Vector>) = results.into_iter()
+            .map(|result| (result.id, Vector::from(result.embedding)))
+            .unzip();
+
+        let mut tx = pool.begin().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO comment_embeddings (comment_id, model, embedding)
+            SELECT u.comment_id, $3, u.embedding
+            FROM UNNEST($1::text[], $2::vector[]) AS u(comment_id, embedding)
+            JOIN comments c ON c.comment_id = u.comment_id
+            ON CONFLICT (comment_id) DO UPDATE
+            SET model = EXCLUDED.model, embedding = EXCLUDED.embedding, updated_at = CURRENT_TIMESTAMP
+            "#
+        )
+            .bind(&comment_ids)
+            .bind(&embeddings)
+            .bind(model)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        tx.commit().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get_by_video_id(pool: &PgPool, video_id: &str) -> Result<Vec<CommentEmbedding>, AppError> {
+        let embeddings = sqlx::query_as::<_, CommentEmbedding>(
+            r#"
+            SELECT e.comment_id, e.embedding
+            FROM comment_embeddings e
+            JOIN comments c ON c.comment_id = e.comment_id
+            WHERE c.video_id = $1
+            ORDER BY c.id ASC
+            "#
+        )
+            .bind(video_id)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(embeddings)
+    }
+
+    // Nearest neighbours by cosine distance. HNSW stops after `hnsw.ef_search` candidates, which
+    // filters can thin out below `limit`, so filtered searches use pgvector's iterative index scan
+    // (0.8+) to keep going until enough rows match. A single video is small enough to rank exactly.
+    pub async fn search(
+        pool: &PgPool,
+        embedding: &Vector,
+        scope: &SearchScope,
+        limit: i64
+    ) -> Result<Vec<SemanticMatch>, AppError> {
+        let exact = scope.video_id.is_some();
+        let filtered = scope.channel_id.is_some() || scope.label.is_some() || scope.exclude_flagged;
+        // pgvector caps ef_search at 1000
+        let ef_search = limit.clamp(40, 1000);
+
+        let mut tx = pool.begin().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        sqlx::query("SELECT set_config('hnsw.ef_search', $1, true)")
+            .bind(ef_search.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        if filtered && !exact {
+            sqlx::query("SELECT set_config('hnsw.iterative_scan', 'strict_order', true)")
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        }
+
+        // A materialized CTE can't be ordered by the index, which forces the exact scan
+        let query = format!(
+            r#"
+            WITH candidates AS {} (
+                SELECT c.comment_id, c.video_id, c.display_name, c.content, c.like_count, c.published_time, c.annotations,
+                       e.embedding <=> $1 AS distance
+                FROM comment_embeddings e
+                JOIN comments c ON c.comment_id = e.comment_id
+                JOIN video_info v ON v.yt_id = c.video_id
+                WHERE ($2::text IS NULL OR c.video_id = $2)
+                  AND ($3::text IS NULL OR v.channel_id = $3)
+                  AND ($4::text IS NULL OR EXISTS (
+                      SELECT 1 FROM jsonb_object_keys(CASE WHEN jsonb_typeof(c.annotations) = 'object' THEN c.annotations ELSE '{{}}'::jsonb END) AS k
+                      WHERE lower(k) = lower($4)
+                  ))
+                  AND ($5::bool IS FALSE OR NOT EXISTS (SELECT 1 FROM comment_flags f WHERE f.comment_id = c.comment_id))
+            )
+            SELECT comment_id, video_id, display_name, content, like_count, published_time, annotations,
+                   1 - distance AS similarity
+            FROM candidates
+            ORDER BY distance
+            LIMIT $6
+            "#,
+            if exact { "MATERIALIZED" } else { "NOT MATERIALIZED" }
+        );
+
+        let matches = sqlx::query_as::<_, SemanticMatch>(&query)
+            .bind(embedding)
+            .bind(scope.video_id.as_deref())
+            .bind(scope.channel_id.as_deref())
+            .bind(scope.label.as_deref())
+            .bind(scope.exclude_flagged)
+            .bind(limit)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        tx.commit().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(matches)
+    }
+}
+
+pub struct SummaryRepository;
+
+impl SummaryRepository {
+    pub async fn create(
+        pool: &PgPool,
+        video_id: &str,
+        model: Option<String>,
+        prompt_version: &str,
+        summary: &str,
+        source_comment_ids: &[String],
+        token_budget: i32
+    ) -> Result<VideoSummary, AppError> {
+        let summary = sqlx::query_as!(
+            VideoSummary,
+            r#"
+            INSERT INTO video_summaries (video_id, model, prompt_version, summary, source_comment_ids, token_budget)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, video_id, model, prompt_version, summary, source_comment_ids, token_budget, created_at
+            "#,
+            video_id,
+            model,
+            prompt_version,
+            summary,
+            source_comment_ids,
+            token_budget
+        )
+            .fetch_one(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(summary)
+    }
+
+    pub async fn get_latest_by_video_id(pool: &PgPool, video_id: &str) -> Result<Option<VideoSummary>, AppError> {
+        let summary = sqlx::query_as!(
+            VideoSummary,
+            r#"
+            SELECT id, video_id, model, prompt_version, summary, source_comment_ids, token_budget, created_at
+            FROM video_summaries
+            WHERE video_id = $1
+            ORDER BY created_at DESC, id DESC
+            LIMIT 1
+            "#,
+            video_id
+        )
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(summary)
+    }
+}
+
+pub struct PresetRepository;
+
+impl PresetRepository {
+    pub async fn create(pool: &PgPool, preset_dto: CreateNerPresetDto) -> Result<NerPreset, AppError> {
+        let preset = sqlx::query_as!(
+            NerPreset,
+            r#"
+            INSERT INTO ner_presets (name, description, labels, threshold)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, name, description, labels, threshold, created_at, updated_at
+            "#,
+            preset_dto.name,
+            preset_dto.description,
+            &preset_dto.labels,
+            preset_dto.threshold
+        )
+            .fetch_one(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(preset)
+    }
+
+    pub async fn get_all(pool: &PgPool) -> Result<Vec<NerPreset>, AppError> {
+        let presets = sqlx::query_as!(
+            NerPreset,
+            r#"
+            SELECT id, name, description, labels, threshold, created_at, updated_at
+            FROM ner_presets
+            ORDER BY name ASC
+            "#
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(presets)
+    }
+
+    pub async fn get_by_name(pool: &PgPool, name: &str) -> Result<Option<NerPreset>, AppError> {
+        let preset = sqlx::query_as!(
+            NerPreset,
+            r#"
+            SELECT id, name, description, labels, threshold, created_at, updated_at
+            FROM ner_presets
+            WHERE name = $1
+            "#,
+            name
+        )
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(preset)
+    }
+
+    pub async fn update(pool: &PgPool, name: &str, preset_dto: CreateNerPresetDto) -> Result<Option<NerPreset>, AppError> {
+        let preset = sqlx::query_as!(
+            NerPreset,
+            r#"
+            UPDATE ner_presets
+            SET name = $2, description = $3, labels = $4, threshold = $5, updated_at = CURRENT_TIMESTAMP
+            WHERE name = $1
+            RETURNING id, name, description, labels, threshold, created_at, updated_at
+            "#,
+            name,
+            preset_dto.name,
+            preset_dto.description,
+            &preset_dto.labels,
+            preset_dto.threshold
+        )
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(preset)
+    }
+
+    pub async fn delete(pool: &PgPool, name: &str) -> Result<u64, AppError> {
+        let result = sqlx::query!(
+            "DELETE FROM ner_presets WHERE name = $1",
+            name
+        )
+            .execute(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn record_annotations(pool: &PgPool, preset_id: i32, comment_ids: &[String]) -> Result<u64, AppError> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO comment_annotation_presets (comment_id, preset_id)
+            SELECT u.comment_id, $2
+            FROM UNNEST($1::text[]) AS u(comment_id)
+            JOIN comments c ON c.comment_id = u.comment_id
+            ON CONFLICT (comment_id, preset_id) DO UPDATE SET annotated_at = CURRENT_TIMESTAMP
+            "#,
+            comment_ids,
+            preset_id
+        )
+            .execute(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get_annotated_comment_ids(pool: &PgPool, preset_id: i32, video_id: &str) -> Result<Vec<String>, AppError> {
+        let comment_ids = sqlx::query_scalar!(
+            r#"
+            SELECT cap.comment_id
+            FROM comment_annotation_presets cap
+            JOIN comments c ON c.comment_id = cap.comment_id
+            WHERE cap.preset_id = $1 AND c.video_id = $2
+            "#,
+            preset_id,
+            video_id
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(comment_ids)
+    }
+}
+
+pub struct AnnotationEditRepository;
+
+impl AnnotationEditRepository {
+    // Records the overrides and their audit entries and stores the corrected annotations in one transaction
+    pub async fn apply(
+        pool: &PgPool,
+        comment_id: &str,
+        editor: &str,
+        note: Option<String>,
+        changes: &[AnnotationChange],
+        annotations: serde_json::Value
+    ) -> Result<Comment, AppError> {
+        let labels: Vec<String> = changes.iter().map(|change| change.label.clone()).collect();
+        let texts: Vec<String> = changes.iter().map(|change| change.text.clone()).collect();
+        let actions: Vec<String> = changes.iter().map(|change| change.action.as_str().to_string()).collect();
+
+        let mut tx = pool.begin().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO annotation_overrides (comment_id, label, text, action, editor)
+            SELECT $1, u.label, u.text, u.action, $5
+            FROM UNNEST($2::text[], $3::text[], $4::text[]) AS u(label, text, action)
+            ON CONFLICT (comment_id, label, text) DO UPDATE
+            SET action = EXCLUDED.action, editor = EXCLUDED.editor, updated_at = CURRENT_TIMESTAMP
+            "#,
+            comment_id,
+            &labels,
+            &texts,
+            &actions,
+            editor
+        )
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO annotation_edits (comment_id, label, text, action, editor, note)
+            SELECT $1, u.label, u.text, u.action, $5, $6
+            FROM UNNEST($2::text[], $3::text[], $4::text[]) AS u(label, text, action)
+            "#,
+            comment_id,
+            &labels,
+            &texts,
+            &actions,
+            editor,
+            note
+        )
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let comment = sqlx::query_as!(
+            Comment,
+            r#"
+            UPDATE comments
+            SET annotations = $2, updated_at = CURRENT_TIMESTAMP
+            WHERE comment_id = $1
+            RETURNING comment_id, channel_id, video_id, display_name, user_verified, thumbnail, content,
+                      published_time, published_at, published_precision, published_edited, language, language_confidence, like_count, reply_count, comment_level, reply_to, reply_order,
+                      annotations, created_at, updated_at, id
+            "#,
+            comment_id,
+            annotations
+        )
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        tx.commit().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(comment)
+    }
+
+    pub async fn get_overrides(pool: &PgPool, comment_ids: &[String]) -> Result<Vec<AnnotationOverride>, AppError> {
+        let overrides = sqlx::query_as!(
+            AnnotationOverride,
+            r#"
+            SELECT comment_id, label, text, action, editor, updated_at
+            FROM annotation_overrides
+            WHERE comment_id = ANY($1)
+            ORDER BY comment_id ASC, label ASC, text ASC
+            "#,
+            comment_ids
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(overrides)
+    }
+
+    pub async fn get_history(pool: &PgPool, comment_id: &str) -> Result<Vec<AnnotationEdit>, AppError> {
+        let edits = sqlx::query_as!(
+            AnnotationEdit,
+            r#"
+            SELECT id, comment_id, label, text, action, editor, note, created_at
+            FROM annotation_edits
+            WHERE comment_id = $1
+            ORDER BY created_at DESC, id DESC
+            "#,
+            comment_id
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(edits)
+    }
+}
+
+pub struct WebhookRepository;
+
+impl WebhookRepository {
+    pub async fn create(pool: &PgPool, webhook_dto: CreateWebhookDto, secret: &str) -> Result<Webhook, AppError> {
+        let webhook = sqlx::query_as!(
+            Webhook,
+            r#"
+            INSERT INTO webhooks (url, secret, events, comment_count_threshold)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, url, secret, events, comment_count_threshold, active, created_at, updated_at
+            "#,
+            webhook_dto.url,
+            secret,
+            &webhook_dto.events,
+            webhook_dto.comment_count_threshold.unwrap_or(1)
+        )
+            .fetch_one(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(webhook)
+    }
+
+    pub async fn get_all(pool: &PgPool) -> Result<Vec<Webhook>, AppError> {
+        let webhooks = sqlx::query_as!(
+            Webhook,
+            r#"
+            SELECT id, url, secret, events, comment_count_threshold, active, created_at, updated_at
+            FROM webhooks
+            ORDER BY id ASC
+            "#
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(webhooks)
+    }
+
+    pub async fn get_by_id(pool: &PgPool, id: i32) -> Result<Option<Webhook>, AppError> {
+        let webhook = sqlx::query_as!(
+            Webhook,
+            r#"
+            SELECT id, url, secret, events, comment_count_threshold, active, created_at, updated_at
+            FROM webhooks
+            WHERE id = $1
+            "#,
+            id
+        )
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(webhook)
+    }
+
+    pub async fn get_subscribed(pool: &PgPool, event: &str) -> Result<Vec<Webhook>, AppError> {
+        let webhooks = sqlx::query_as!(
+            Webhook,
+            r#"
+            SELECT id, url, secret, events, comment_count_threshold, active, created_at, updated_at
+            FROM webhooks
+            WHERE active AND (cardinality(events) = 0 OR $1 = ANY(events))
+            ORDER BY id ASC
+            "#,
+            event
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(webhooks)
+    }
+
+    pub async fn delete(pool: &PgPool, id: i32) -> Result<u64, AppError> {
+        let result = sqlx::query!("DELETE FROM webhooks WHERE id = $1", id)
+            .execute(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn create_delivery(
+        pool: &PgPool,
+        webhook_id: i32,
+        event: &str,
+        payload: &serde_json::Value,
+        replay_of: Option<i32>
+    ) -> Result<WebhookDelivery, AppError> {
+        let delivery = sqlx::query_as!(
+            WebhookDelivery,
+            r#"
+            INSERT INTO webhook_deliveries (webhook_id, event, payload, status, replay_of)
+            VALUES ($1, $2, $3, 'pending', $4)
+            RETURNING id, webhook_id, event, payload, status, attempts, response_status, last_error, replay_of, created_at, delivered_at
+            "#,
+            webhook_id,
+            event,
+            payload,
+            replay_of
+        )
+            .fetch_one(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(delivery)
+    }
+
+    pub async fn get_delivery(pool: &PgPool, id: i32) -> Result<Option<WebhookDelivery>, AppError> {
+        let delivery = sqlx::query_as!(
+            WebhookDelivery,
+            r#"
+            SELECT id, webhook_id, event, payload, status, attempts, response_status, last_error, replay_of, created_at, delivered_at
+            FROM webhook_deliveries
+            WHERE id = $1
+            "#,
+            id
+        )
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(delivery)
+    }
+
+    pub async fn get_deliveries(pool: &PgPool, webhook_id: i32, pagination: &Pagination) -> Result<Vec<WebhookDelivery>, AppError> {
+        let deliveries = sqlx::query_as!(
+            WebhookDelivery,
+            r#"
+            SELECT id, webhook_id, event, payload, status, attempts, response_status, last_error, replay_of, created_at, delivered_at
+            FROM webhook_deliveries
+            WHERE webhook_id = $1
+            ORDER BY created_at DESC, id DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            webhook_id,
+            pagination.limit(),
+            pagination.offset()
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(deliveries)
+    }
+
+    // `status` stays 'pending' while retries remain
+    pub async fn record_attempt(
+        pool: &PgPool,
+        id: i32,
+        status: &str,
+        response_status: Option<i32>,
+        error: Option<String>
+    ) -> Result<(), AppError> {
+        sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET attempts = attempts + 1,
+                status = $2::varchar,
+                response_status = $3,
+                last_error = $4,
+                delivered_at = CASE WHEN $2::varchar = 'delivered' THEN CURRENT_TIMESTAMP ELSE delivered_at END
+            WHERE id = $1
+            "#,
+            id,
+            status,
+            response_status,
+            error
+        )
+            .execute(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+pub struct AuthorRepository;
+
+impl AuthorRepository {
+    // Comments are grouped by author first, since one upsert can't touch the same row twice
+    pub async fn upsert_from_comments(pool: &PgPool, comments: &[Comment], seen_at: DateTime<Utc>) -> Result<u64, AppError> {
+        let mut authors: HashMap<&str, (&Comment, DateTime<Utc>, DateTime<Utc>)> = HashMap::new();
+        for comment in comments {
+            let at = comment.published_at.unwrap_or(seen_at);
+            authors.entry(&comment.channel_id)
+                .and_modify(|(_, first, last)| {
+                    *first = (*first).min(at);
+                    *last = (*last).max(at);
+                })
+                .or_insert((comment, at, at));
+        }
+
+        let mut channel_ids = Vec::with_capacity(authors.len());
+        let mut display_names = Vec::with_capacity(authors.len());
+        let mut thumbnails: Vec<Option<String>> = Vec::with_capacity(authors.len());
+        let mut verified: Vec<Option<bool>> = Vec::with_capacity(authors.len());
+        let mut first_seen = Vec::with_capacity(authors.len());
+        let mut last_seen = Vec::with_capacity(authors.len());
+        for (channel_id, (comment, first, last)) in authors {
+            channel_ids.push(channel_id.to_string());
+            display_names.push(comment.display_name.clone());
+            thumbnails.push(comment.thumbnail.clone());
+            verified.push(comment.user_verified);
+            first_seen.push(first);
+            last_seen.push(last);
+        }
+
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO authors (channel_id, display_name, thumbnail, user_verified, first_seen_at, last_seen_at)
+            SELECT * FROM UNNEST($1::text[], $2::text[], $3::text[], $4::bool[], $5::timestamptz[], $6::timestamptz[])
+            ON CONFLICT (channel_id) DO UPDATE
+            SET display_name = EXCLUDED.display_name,
+                thumbnail = EXCLUDED.thumbnail,
+                user_verified = EXCLUDED.user_verified,
+                first_seen_at = LEAST(authors.first_seen_at, EXCLUDED.first_seen_at),
+                last_seen_at = GREATEST(authors.last_seen_at, EXCLUDED.last_seen_at),
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+            &channel_ids,
+            &display_names,
+            &thumbnails as &[Option<String>],
+            &verified as &[Option<bool>],
+            &first_seen,
+            &last_seen
+        )
+            .execute(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get_by_channel_id(pool: &PgPool, channel_id: &str) -> Result<Option<Author>, AppError> {
+        let author = sqlx::query_as!(
+            Author,
+            r#"
+            SELECT channel_id, display_name, thumbnail, user_verified, first_seen_at, last_seen_at, created_at, updated_at
+            FROM authors
+            WHERE channel_id = $1
+            "#,
+            channel_id
+        )
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(author)
+    }
+
+    // Likes and replies are the totals received across every comment currently stored for the author
+    pub async fn get_stats(pool: &PgPool, channel_id: &str) -> Result<AuthorStats, AppError> {
+        let stats = sqlx::query_as!(
+            AuthorStats,
+            r#"
+            SELECT COUNT(*) AS "comment_count!",
+                   COUNT(DISTINCT video_id) AS "video_count!",
+                   COALESCE(SUM(like_count), 0)::bigint AS "total_likes!",
+                   COALESCE(SUM(reply_count), 0)::bigint AS "total_replies!"
+            FROM comments
+            WHERE channel_id = $1
+            "#,
+            channel_id
+        )
+            .fetch_one(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(stats)
+    }
+
+    pub async fn get_videos(pool: &PgPool, channel_id: &str) -> Result<Vec<AuthorVideo>, AppError> {
+        let videos = sqlx::query_as!(
+            AuthorVideo,
+            r#"
+            SELECT c.video_id AS "video_id!",
+                   v.title AS "title?",
+                   COUNT(*) AS "comment_count!",
+                   COALESCE(SUM(c.like_count), 0)::bigint AS "total_likes!",
+                   MAX(c.published_at) AS "last_comment_at?"
+            FROM comments c
+            LEFT JOIN video_info v ON v.yt_id = c.video_id
+            WHERE c.channel_id = $1
+            GROUP BY c.video_id, v.title
+            ORDER BY COUNT(*) DESC, c.video_id ASC
+            "#,
+            channel_id
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(videos)
+    }
+
+    // Counts each (label, text) pair once per comment, like `get_ranked_annotations`
+    pub async fn get_top_entities(pool: &PgPool, channel_id: &str, limit: i64) -> Result<Vec<AuthorEntity>, AppError> {
+        let entities = sqlx::query_as!(
+            AuthorEntity,
+            r#"
+            WITH mentions AS (
+                SELECT DISTINCT c.comment_id, lower(a.key) AS label, e.text
+                FROM comments c
+                CROSS JOIN LATERAL jsonb_each(
+                    CASE WHEN jsonb_typeof(c.annotations) = 'object' THEN c.annotations ELSE '{}'::jsonb END
+                ) AS a(key, value)
+                CROSS JOIN LATERAL jsonb_array_elements_text(
+                    CASE WHEN jsonb_typeof(a.value) = 'array' THEN a.value ELSE jsonb_build_array(a.value) END
+                ) AS e(text)
+                WHERE c.channel_id = $1 AND e.text <> ''
+            )
+            SELECT label AS "label!", text AS "text!", COUNT(*) AS "count!"
+            FROM mentions
+            GROUP BY label, text
+            ORDER BY COUNT(*) DESC, label ASC, text ASC
+            LIMIT $2
+            "#,
+            channel_id,
+            limit
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(entities)
+    }
+}
+
+pub struct AnalyticsRepository;
+
+impl AnalyticsRepository {
+    // `sort` is one of comments, likes, replies or thread_replies; anything else sorts by comments
+    pub async fn get_commenters(
+        pool: &PgPool,
+        video_id: &str,
+        sort: &str,
+        pagination: &Pagination,
+        exclude_flagged: bool
+    ) -> Result<(Vec<CommenterStats>, i64), AppError> {
+        let commenters = sqlx::query_as!(
+            CommenterStats,
+            r#"
+            WITH scoped AS (
+                SELECT c.*
+                FROM comments c
+                WHERE c.video_id = $1
+                  AND ($5::bool IS FALSE OR NOT EXISTS (SELECT 1 FROM comment_flags f WHERE f.comment_id = c.comment_id))
+            ),
+            threads AS (
+                SELECT t.channel_id,
+                       COUNT(r.comment_id) AS replies,
+                       COUNT(DISTINCT r.channel_id) FILTER (WHERE r.channel_id <> t.channel_id) AS repliers
+                FROM scoped t
+                LEFT JOIN scoped r ON r.reply_to = t.comment_id
+                WHERE t.comment_level = 0
+                GROUP BY t.channel_id
+            ),
+            commenters AS (
+                SELECT c.channel_id,
+                       (array_agg(c.display_name ORDER BY c.id DESC))[1] AS display_name,
+                       bool_or(COALESCE(c.user_verified, FALSE)) AS user_verified,
+                       COUNT(*) AS comment_count,
+                       COALESCE(SUM(c.like_count), 0)::bigint AS total_likes,
+                       COALESCE(SUM(c.reply_count), 0)::bigint AS replies_received,
+                       COUNT(*) FILTER (WHERE c.comment_level = 0) AS threads_started
+                FROM scoped c
+                GROUP BY c.channel_id
+            )
+            SELECT c.channel_id AS "channel_id!",
+                   c.display_name AS "display_name!",
+                   c.user_verified AS "user_verified!",
+                   c.comment_count AS "comment_count!",
+                   c.total_likes AS "total_likes!",
+                   c.replies_received AS "replies_received!",
+                   c.threads_started AS "threads_started!",
+                   COALESCE(t.replies, 0) AS "thread_replies!",
+                   COALESCE(t.repliers, 0) AS "thread_repliers!"
+            FROM commenters c
+            LEFT JOIN threads t ON t.channel_id = c.channel_id
+            ORDER BY CASE $2
+                         WHEN 'likes' THEN c.total_likes
+                         WHEN 'replies' THEN c.replies_received
+                         WHEN 'thread_replies' THEN COALESCE(t.replies, 0)
+                         ELSE c.comment_count
+                     END DESC,
+                     c.comment_count DESC,
+                     c.channel_id ASC
+            LIMIT $3 OFFSET $4
+            "#,
+            video_id,
+            sort,
+            pagination.limit(),
+            pagination.offset(),
+            exclude_flagged
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(DISTINCT channel_id) AS "count!"
+            FROM comments
+            WHERE video_id = $1
+              AND ($2::bool IS FALSE OR NOT EXISTS (SELECT 1 FROM comment_flags f WHERE f.comment_id = comments.comment_id))
+            "#,
+            video_id,
+            exclude_flagged
+        )
+            .fetch_one(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok((commenters, total))
+    }
+
+    // Creator comments are the ones posted from the video's own channel
+    pub async fn get_comment_stats(
+        pool: &PgPool,
+        video_id: &str,
+        creator_channel_id: &str,
+        exclude_flagged: bool
+    ) -> Result<VideoCommentStats, AppError> {
+        let stats = sqlx::query_as!(
+            VideoCommentStats,
+            r#"
+            SELECT COUNT(*) AS "scraped_comments!",
+                   COUNT(*) FILTER (WHERE COALESCE(comment_level, 0) = 0) AS "top_level_comments!",
+                   COUNT(*) FILTER (WHERE comment_level > 0) AS "replies!",
+                   COUNT(DISTINCT channel_id) AS "unique_authors!",
+                   COUNT(DISTINCT channel_id) FILTER (WHERE user_verified) AS "verified_authors!",
+                   COUNT(*) FILTER (WHERE channel_id = $2) AS "creator_comments!",
+                   COUNT(*) FILTER (WHERE channel_id = $2 AND comment_level > 0) AS "creator_replies!",
+                   AVG(char_length(content))::float8 AS "average_length",
+                   COALESCE(SUM(like_count), 0)::bigint AS "total_likes!",
+                   percentile_cont(0.25) WITHIN GROUP (ORDER BY like_count) AS "likes_p25",
+                   percentile_cont(0.5) WITHIN GROUP (ORDER BY like_count) AS "likes_p50",
+                   percentile_cont(0.75) WITHIN GROUP (ORDER BY like_count) AS "likes_p75",
+                   percentile_cont(0.9) WITHIN GROUP (ORDER BY like_count) AS "likes_p90",
+                   percentile_cont(0.99) WITHIN GROUP (ORDER BY like_count) AS "likes_p99",
+                   MAX(like_count) AS "max_likes"
+            FROM comments
+            WHERE video_id = $1
+              AND ($3::bool IS FALSE OR NOT EXISTS (SELECT 1 FROM comment_flags f WHERE f.comment_id = comments.comment_id))
+            "#,
+            video_id,
+            creator_channel_id,
+            exclude_flagged
+        )
+            .fetch_one(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(stats)
+    }
+
+    pub async fn get_depth_distribution(pool: &PgPool, video_id: &str, exclude_flagged: bool) -> Result<Vec<DepthCount>, AppError> {
+        let depths = sqlx::query_as!(
+            DepthCount,
+            r#"
+            SELECT COALESCE(comment_level, 0) AS "depth!", COUNT(*) AS "count!"
+            FROM comments
+            WHERE video_id = $1
+              AND ($2::bool IS FALSE OR NOT EXISTS (SELECT 1 FROM comment_flags f WHERE f.comment_id = comments.comment_id))
+            GROUP BY 1
+            ORDER BY 1 ASC
+            "#,
+            video_id,
+            exclude_flagged
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(depths)
+    }
+
+    pub async fn get_most_replied_threads(
+        pool: &PgPool,
+        video_id: &str,
+        limit: i64,
+        exclude_flagged: bool
+    ) -> Result<Vec<RepliedThread>, AppError> {
+        let threads = sqlx::query_as!(
+            RepliedThread,
+            r#"
+            SELECT t.comment_id, t.channel_id, t.display_name, t.content, t.like_count, t.reply_count,
+                   COUNT(r.comment_id) AS "scraped_replies!"
+            FROM comments t
+            LEFT JOIN comments r ON r.reply_to = t.comment_id AND r.video_id = t.video_id
+            WHERE t.video_id = $1 AND COALESCE(t.comment_level, 0) = 0
+              AND ($3::bool IS FALSE OR NOT EXISTS (SELECT 1 FROM comment_flags f WHERE f.comment_id = t.comment_id))
+            GROUP BY t.id
+            ORDER BY GREATEST(COALESCE(t.reply_count, 0), COUNT(r.comment_id)) DESC, t.like_count DESC NULLS LAST, t.id ASC
+            LIMIT $2
+            "#,
+            video_id,
+            limit,
+            exclude_flagged
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(threads)
+    }
+
+    // Authors who commented on at least two of the videos, most widespread first. Each author is shown
+    // under the display name of their most recent comment across the videos
+    pub async fn get_shared_commenters(
+        pool: &PgPool,
+        video_ids: &[String],
+        limit: i64,
+        exclude_flagged: bool
+    ) -> Result<Vec<SharedCommenter>, AppError> {
+        let commenters = sqlx::query_as!(
+            SharedCommenter,
+            r#"
+            WITH per_video AS (
+                SELECT channel_id, video_id, COUNT(*) AS count, MAX(id) AS last_id,
+                       (array_agg(display_name ORDER BY id DESC))[1] AS display_name
+                FROM comments
+                WHERE video_id = ANY($1)
+                  AND ($3::bool IS FALSE OR NOT EXISTS (SELECT 1 FROM comment_flags f WHERE f.comment_id = comments.comment_id))
+                GROUP BY channel_id, video_id
+            )
+            SELECT channel_id AS "channel_id!",
+                   (array_agg(display_name ORDER BY last_id DESC))[1] AS "display_name!",
+                   COUNT(*) AS "video_count!",
+                   SUM(count)::bigint AS "comment_count!",
+                   jsonb_object_agg(video_id, count) AS "videos!"
+            FROM per_video
+            GROUP BY channel_id
+            HAVING COUNT(*) >= 2
+            ORDER BY COUNT(*) DESC, SUM(count) DESC, channel_id ASC
+            LIMIT $2
+            "#,
+            video_ids,
+            limit,
+            exclude_flagged
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(commenters)
+    }
+
+    // One row per pair of videos with at least one author in common, with `video_a` < `video_b`
+    pub async fn get_shared_commenter_pairs(
+        pool: &PgPool,
+        video_ids: &[String],
+        exclude_flagged: bool
+    ) -> Result<Vec<SharedCommenterPair>, AppError> {
+        let pairs = sqlx::query_as!(
+            SharedCommenterPair,
+            r#"
+            WITH authors AS (
+                SELECT DISTINCT video_id, channel_id
+                FROM comments
+                WHERE video_id = ANY($1)
+                  AND ($2::bool IS FALSE OR NOT EXISTS (SELECT 1 FROM comment_flags f WHERE f.comment_id = comments.comment_id))
+            )
+            SELECT a.video_id AS "video_a!", b.video_id AS "video_b!", COUNT(*) AS "shared!"
+            FROM authors a
+            JOIN authors b ON b.channel_id = a.channel_id AND a.video_id < b.video_id
+            GROUP BY a.video_id, b.video_id
+            "#,
+            video_ids,
+            exclude_flagged
+        )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(pairs)
+    }
+}
@@ -0,0 +1,120 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+
+// Subscribers that fall this far behind skip the oldest events rather than slowing publishers down
+const EVENT_BUFFER: usize = 256;
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressEventKind {
+    Started,
+    Progress,
+    Completed,
+    Failed
+}
+
+impl ProgressEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProgressEventKind::Started => "started",
+            ProgressEventKind::Progress => "progress",
+            ProgressEventKind::Completed => "completed",
+            ProgressEventKind::Failed => "failed"
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    VideoExtraction,
+    Ner
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgressEvent {
+    pub kind: ProgressEventKind,
+    pub operation: Operation,
+    pub video_id: String,
+    // Set for background NER runs, matching `/ner/runs/{run_id}`
+    pub run_id: Option<Uuid>,
+    pub stage: Option<String>,
+    pub processed: usize,
+    pub total: Option<usize>,
+    pub message: Option<String>,
+    pub at: DateTime<Utc>
+}
+
+// Events are only delivered to clients connected when they're published; nothing is stored.
+#[derive(Debug, Clone)]
+pub struct EventBus(broadcast::Sender<ProgressEvent>);
+
+impl Default for EventBus {
+    fn default() -> Self {
+        EventBus(broadcast::channel(EVENT_BUFFER).0)
+    }
+}
+
+impl EventBus {
+    // Publishing with no subscribers is not an error
+    pub fn publish(&self, event: ProgressEvent) {
+        let _ = self.0.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ProgressEvent> {
+        self.0.subscribe()
+    }
+
+    pub fn reporter(&self, operation: Operation, video_id: &str, run_id: Option<Uuid>) -> ProgressReporter {
+        ProgressReporter {
+            events: self.clone(),
+            operation,
+            video_id: video_id.to_string(),
+            run_id
+        }
+    }
+}
+
+// Publishes the events for one operation on one video
+#[derive(Debug, Clone)]
+pub struct ProgressReporter {
+    events: EventBus,
+    operation: Operation,
+    video_id: String,
+    run_id: Option<Uuid>
+}
+
+impl ProgressReporter {
+    fn publish(&self, kind: ProgressEventKind, stage: Option<&str>, processed: usize, total: Option<usize>, message: Option<String>) {
+        self.events.publish(ProgressEvent {
+            kind,
+            operation: self.operation,
+            video_id: self.video_id.clone(),
+            run_id: self.run_id,
+            stage: stage.map(str::to_string),
+            processed,
+            total,
+            message,
+            at: Utc::now()
+        });
+    }
+
+    pub fn started(&self, total: Option<usize>) {
+        self.publish(ProgressEventKind::Started, None, 0, total, None);
+    }
+
+    pub fn progress(&self, stage: &str, processed: usize, total: Option<usize>) {
+        self.publish(ProgressEventKind::Progress, Some(stage), processed, total, None);
+    }
+
+    pub fn completed(&self, processed: usize, total: Option<usize>) {
+        self.publish(ProgressEventKind::Completed, None, processed, total, None);
+    }
+
+    pub fn failed(&self, processed: usize, total: Option<usize>, message: String) {
+        self.publish(ProgressEventKind::Failed, None, processed, total, Some(message));
+    }
+}
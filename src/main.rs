@@ -1,4 +1,4 @@
-use axum::{Router, routing::{get, post}};
+use axum::{Router, routing::{get, post, patch, delete}};
 use error::AppError;
 use tower_http::{
     cors::CorsLayer,
@@ -11,6 +11,9 @@ mod routes;
 mod db;
 mod ai;
 mod error;
+mod events;
+mod webhooks;
+mod utils;
 
 use crate::db::connection::{get_connection, AppState};
 
@@ -33,13 +36,44 @@ async fn main() {
     let app = Router::new()
         .route("/", get(hello_world))
         .route("/health", get(routes::health::health_check))
+        .route("/events", get(routes::events_route::get_events))
         .route("/video-extraction", post(routes::video::video_extraction))
         .route("/videos", get(routes::video::get_videos))
+        .route("/videos/compare", post(routes::analytics_route::compare_videos_route))
         .route("/videos/{yt_id}", get(routes::video::get_video_by_id))
         .route("/videos/{yt_id}/comments", get(routes::video::get_comments_by_video_id))
+        .route("/videos/{yt_id}/comments/export", get(routes::export_route::export_video_comments))
+        .route("/videos/{yt_id}/entities/graph", get(routes::entity::get_entity_graph))
+        .route("/videos/{yt_id}/entities/timeline", get(routes::entity::get_entity_timeline))
+        .route("/videos/{yt_id}/sentiment", get(routes::sentiment_route::get_video_sentiment))
+        .route("/videos/{yt_id}/flags", get(routes::moderation_route::get_video_flags))
+        .route("/videos/{yt_id}/topics", get(routes::topics_route::get_video_topics))
+        .route("/videos/{yt_id}/analytics", get(routes::analytics_route::get_video_analytics))
+        .route("/videos/{yt_id}/analytics/commenters", get(routes::analytics_route::get_video_commenters))
+        .route("/videos/{yt_id}/summary", get(routes::summary_route::get_video_summary).post(routes::summary_route::create_video_summary))
+        .route("/comments/{comment_id}/annotations", patch(routes::annotation_route::patch_comment_annotations))
+        .route("/comments/{comment_id}/annotations/history", get(routes::annotation_route::get_comment_annotation_history))
+        .route("/entities/{label}/{text}/comments", get(routes::entity::get_comments_by_entity))
+        .route("/authors/{channel_id}", get(routes::author_route::get_author))
         .route("/reset-database", post(routes::database::reset_database))
         .route("/ner", post(routes::ner_route::ner_operation))
+        .route("/ner/presets", get(routes::preset_route::get_presets).post(routes::preset_route::create_preset))
+        .route("/ner/presets/{name}", get(routes::preset_route::get_preset).put(routes::preset_route::update_preset).delete(routes::preset_route::delete_preset))
+        .route("/ner/runs/{run_id}", get(routes::ner_route::get_ner_run))
+        .route("/ner/runs/{run_id}/cancel", post(routes::ner_route::cancel_ner_run))
         .route("/ner/ranked_annotations", post(routes::ner_route::get_ranked_annotations_route))
+        .route("/ner/ranked_annotations/aggregate", post(routes::ner_route::get_aggregated_ranked_annotations_route))
+        .route("/datasets/ner", get(routes::dataset_route::export_ner_dataset))
+        .route("/exports/videos", get(routes::export_route::export_videos))
+        .route("/exports/comments", get(routes::export_route::export_comments))
+        .route("/sentiment", post(routes::sentiment_route::sentiment_operation))
+        .route("/moderation", post(routes::moderation_route::moderation_operation))
+        .route("/embeddings", post(routes::topics_route::embedding_operation))
+        .route("/search/semantic", post(routes::search_route::semantic_search_route))
+        .route("/webhooks", get(routes::webhook_route::get_webhooks).post(routes::webhook_route::create_webhook))
+        .route("/webhooks/{id}", delete(routes::webhook_route::delete_webhook))
+        .route("/webhooks/{id}/deliveries", get(routes::webhook_route::get_webhook_deliveries))
+        .route("/webhooks/deliveries/{delivery_id}/replay", post(routes::webhook_route::replay_webhook_delivery))
         .layer(CorsLayer::permissive())
         .layer(
             TraceLayer::new_for_http()
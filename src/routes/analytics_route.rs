@@ -0,0 +1,144 @@
+use axum::{Json, extract::{State, Path, Query}};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use crate::db::{
+    connection::AppState,
+    models::Pagination,
+    operations::{VideoInfoRepository, AnalyticsRepository}
+};
+use crate::ai::comparison::{compare_videos, CompareRequest, VideoComparison};
+use crate::routes::errors::AppError;
+
+
+#[derive(Debug, Clone, Copy, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CommenterSort {
+    #[default]
+    Comments,
+    Likes,
+    Replies,
+    ThreadReplies
+}
+
+impl CommenterSort {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CommenterSort::Comments => "comments",
+            CommenterSort::Likes => "likes",
+            CommenterSort::Replies => "replies",
+            CommenterSort::ThreadReplies => "thread_replies"
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CommentersQuery {
+    #[serde(default)]
+    sort: CommenterSort,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    #[serde(default)]
+    exclude_flagged: bool
+}
+
+pub async fn get_video_commenters(
+    State(app_state): State<AppState>,
+    Path(yt_id): Path<String>,
+    Query(query): Query<CommentersQuery>
+) -> Result<Json<Value>, AppError> {
+    VideoInfoRepository::get_by_yt_id(&app_state.db_pool, &yt_id).await?
+        .ok_or_else(|| AppError::InvalidInput("Video not found".to_string()))?;
+
+    let pagination = Pagination { limit: query.limit, offset: query.offset };
+    let (commenters, total) = AnalyticsRepository::get_commenters(
+        &app_state.db_pool,
+        &yt_id,
+        query.sort.as_str(),
+        &pagination,
+        query.exclude_flagged
+    ).await?;
+
+    let response = json!({
+        "video_id": yt_id,
+        "sort": query.sort.as_str(),
+        "commenters": commenters,
+        "count": commenters.len(),
+        "total": total,
+        "limit": pagination.limit(),
+        "offset": pagination.offset()
+    });
+
+    Ok(Json(response))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AnalyticsQuery {
+    thread_limit: Option<i64>,
+    #[serde(default)]
+    exclude_flagged: bool
+}
+
+fn ratio(part: i64, whole: i64) -> Option<f64> {
+    (whole > 0).then(|| part as f64 / whole as f64)
+}
+
+// `scraped_ratio` compares the comments stored against the count YouTube reports for the video
+pub async fn get_video_analytics(
+    State(app_state): State<AppState>,
+    Path(yt_id): Path<String>,
+    Query(query): Query<AnalyticsQuery>
+) -> Result<Json<Value>, AppError> {
+    let video = VideoInfoRepository::get_by_yt_id(&app_state.db_pool, &yt_id).await?
+        .ok_or_else(|| AppError::InvalidInput("Video not found".to_string()))?;
+
+    let thread_limit = query.thread_limit.unwrap_or(10).clamp(1, 100);
+
+    let stats = AnalyticsRepository::get_comment_stats(&app_state.db_pool, &yt_id, &video.channel_id, query.exclude_flagged).await?;
+    let depths = AnalyticsRepository::get_depth_distribution(&app_state.db_pool, &yt_id, query.exclude_flagged).await?;
+    let threads = AnalyticsRepository::get_most_replied_threads(&app_state.db_pool, &yt_id, thread_limit, query.exclude_flagged).await?;
+
+    let response = json!({
+        "video_id": yt_id,
+        "comments": {
+            "reported": video.comment_count,
+            "scraped": stats.scraped_comments,
+            "scraped_ratio": ratio(stats.scraped_comments, video.comment_count),
+            "top_level": stats.top_level_comments,
+            "replies": stats.replies,
+            "average_length": stats.average_length
+        },
+        "reply_depth": depths,
+        "likes": {
+            "total": stats.total_likes,
+            "max": stats.max_likes,
+            "percentiles": {
+                "p25": stats.likes_p25,
+                "p50": stats.likes_p50,
+                "p75": stats.likes_p75,
+                "p90": stats.likes_p90,
+                "p99": stats.likes_p99
+            }
+        },
+        "authors": {
+            "unique": stats.unique_authors,
+            "verified": stats.verified_authors,
+            "verified_share": ratio(stats.verified_authors, stats.unique_authors)
+        },
+        "creator": {
+            "channel_id": video.channel_id,
+            "comments": stats.creator_comments,
+            "replies": stats.creator_replies
+        },
+        "most_replied_threads": threads
+    });
+
+    Ok(Json(response))
+}
+
+pub async fn compare_videos_route(
+    State(app_state): State<AppState>,
+    Json(payload): Json<CompareRequest>
+) -> Result<Json<VideoComparison>, AppError> {
+    let comparison = compare_videos(&payload, State(app_state)).await?;
+    Ok(Json(comparison))
+}
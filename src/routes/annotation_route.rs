@@ -0,0 +1,25 @@
+use axum::{Json, extract::{State, Path}};
+use crate::db::{
+    connection::AppState,
+    models::Comment
+};
+use crate::ai::annotation_edits::{edit_annotations, get_annotation_history, AnnotationEditRequest, AnnotationHistory};
+use crate::routes::errors::AppError;
+
+
+pub async fn patch_comment_annotations(
+    State(app_state): State<AppState>,
+    Path(comment_id): Path<String>,
+    Json(payload): Json<AnnotationEditRequest>
+) -> Result<Json<Comment>, AppError> {
+    let comment = edit_annotations(&comment_id, payload, State(app_state)).await?;
+    Ok(Json(comment))
+}
+
+pub async fn get_comment_annotation_history(
+    State(app_state): State<AppState>,
+    Path(comment_id): Path<String>
+) -> Result<Json<AnnotationHistory>, AppError> {
+    let history = get_annotation_history(&comment_id, State(app_state)).await?;
+    Ok(Json(history))
+}
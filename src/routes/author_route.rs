@@ -0,0 +1,49 @@
+use axum::{Json, extract::{State, Path, Query}};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use crate::db::{
+    connection::AppState,
+    models::Pagination,
+    operations::{AuthorRepository, CommentRepository}
+};
+use crate::routes::errors::AppError;
+
+
+#[derive(Debug, Deserialize)]
+pub struct AuthorQuery {
+    limit: Option<i64>,
+    offset: Option<i64>,
+    entity_limit: Option<i64>
+}
+
+// `comments` is the paginated history; `stats`, `videos` and `top_entities` cover everything stored
+pub async fn get_author(
+    State(app_state): State<AppState>,
+    Path(channel_id): Path<String>,
+    Query(query): Query<AuthorQuery>
+) -> Result<Json<Value>, AppError> {
+    let author = AuthorRepository::get_by_channel_id(&app_state.db_pool, &channel_id).await?
+        .ok_or_else(|| AppError::InvalidInput("Author not found".to_string()))?;
+
+    let pagination = Pagination { limit: query.limit, offset: query.offset };
+    let entity_limit = query.entity_limit.unwrap_or(10).clamp(1, 100);
+
+    let stats = AuthorRepository::get_stats(&app_state.db_pool, &channel_id).await?;
+    let videos = AuthorRepository::get_videos(&app_state.db_pool, &channel_id).await?;
+    let top_entities = AuthorRepository::get_top_entities(&app_state.db_pool, &channel_id, entity_limit).await?;
+    let comments = CommentRepository::get_by_channel_id(&app_state.db_pool, &channel_id, &pagination).await?;
+
+    let response = json!({
+        "author": author,
+        "stats": stats,
+        "videos": videos,
+        "top_entities": top_entities,
+        "comments": comments,
+        "count": comments.len(),
+        "total": stats.comment_count,
+        "limit": pagination.limit(),
+        "offset": pagination.offset()
+    });
+
+    Ok(Json(response))
+}
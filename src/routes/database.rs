@@ -8,6 +8,17 @@ pub async fn reset_database(State(app_state): State<AppState>) -> Result<Json<Va
     tracing::info!("Starting database reset operation");
 
     let drop_queries = vec![
+        "DROP TABLE IF EXISTS authors CASCADE;",
+        "DROP TABLE IF EXISTS webhook_deliveries CASCADE;",
+        "DROP TABLE IF EXISTS webhooks CASCADE;",
+        "DROP TABLE IF EXISTS annotation_overrides CASCADE;",
+        "DROP TABLE IF EXISTS annotation_edits CASCADE;",
+        "DROP TABLE IF EXISTS comment_annotation_presets CASCADE;",
+        "DROP TABLE IF EXISTS ner_presets CASCADE;",
+        "DROP TABLE IF EXISTS video_summaries CASCADE;",
+        "DROP TABLE IF EXISTS comment_sentiment CASCADE;",
+        "DROP TABLE IF EXISTS comment_flags CASCADE;",
+        "DROP TABLE IF EXISTS comment_embeddings CASCADE;",
         "DROP TABLE IF EXISTS comments CASCADE;",
         "DROP TABLE IF EXISTS video_info CASCADE;",
     ];
@@ -26,6 +37,8 @@ pub async fn reset_database(State(app_state): State<AppState>) -> Result<Json<Va
         like_count BIGINT NOT NULL DEFAULT 0,
         video_thumbnail VARCHAR,
         upload_date VARCHAR,
+        uploaded_at TIMESTAMPTZ,
+        upload_date_precision VARCHAR,
         channel_thumbnail VARCHAR,
         created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
         updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
@@ -48,6 +61,11 @@ pub async fn reset_database(State(app_state): State<AppState>) -> Result<Json<Va
         thumbnail VARCHAR,
         content TEXT NOT NULL,
         published_time VARCHAR,
+        published_at TIMESTAMPTZ,
+        published_precision VARCHAR,
+        published_edited BOOLEAN NOT NULL DEFAULT FALSE,
+        language VARCHAR,
+        language_confidence REAL,
         like_count INTEGER DEFAULT 0,
         reply_count INTEGER DEFAULT 0,
         comment_level INTEGER DEFAULT 0,
@@ -73,6 +91,169 @@ pub async fn reset_database(State(app_state): State<AppState>) -> Result<Json<Va
     "#,
         r#"
     CREATE INDEX idx_comments_annotations_gin ON comments USING GIN (annotations jsonb_path_ops);
+    "#,
+        r#"
+    CREATE INDEX idx_comments_published_at ON comments(video_id, published_at);
+    "#,
+        r#"
+    CREATE INDEX idx_comments_language ON comments(video_id, language);
+    "#,
+        r#"
+    CREATE TABLE comment_sentiment (
+        comment_id VARCHAR PRIMARY KEY,
+        label VARCHAR NOT NULL,
+        polarity REAL NOT NULL,
+        scores JSONB NOT NULL DEFAULT '{}'::jsonb,
+        emotions JSONB NOT NULL DEFAULT '{}'::jsonb,
+        created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+        FOREIGN KEY (comment_id) REFERENCES comments(comment_id) ON DELETE CASCADE
+    );
+    "#,
+        r#"
+    CREATE INDEX idx_comment_sentiment_label ON comment_sentiment(label);
+    "#,
+        r#"
+    CREATE TABLE comment_flags (
+        id SERIAL PRIMARY KEY,
+        comment_id VARCHAR NOT NULL,
+        source VARCHAR NOT NULL,
+        reason VARCHAR NOT NULL,
+        score REAL,
+        detail TEXT,
+        created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+        UNIQUE (comment_id, source, reason),
+        FOREIGN KEY (comment_id) REFERENCES comments(comment_id) ON DELETE CASCADE
+    );
+    "#,
+        r#"
+    CREATE INDEX idx_comment_flags_comment_id ON comment_flags(comment_id);
+    "#,
+        r#"
+    CREATE EXTENSION IF NOT EXISTS vector;
+    "#,
+        r#"
+    CREATE TABLE comment_embeddings (
+        comment_id VARCHAR PRIMARY KEY,
+        model VARCHAR,
+        embedding vector(384) NOT NULL,
+        created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+        FOREIGN KEY (comment_id) REFERENCES comments(comment_id) ON DELETE CASCADE
+    );
+    "#,
+        r#"
+    CREATE INDEX idx_comment_embeddings_hnsw ON comment_embeddings USING hnsw (embedding vector_cosine_ops);
+    "#,
+        r#"
+    CREATE TABLE video_summaries (
+        id SERIAL PRIMARY KEY,
+        video_id VARCHAR NOT NULL,
+        model VARCHAR,
+        prompt_version VARCHAR NOT NULL,
+        summary TEXT NOT NULL,
+        source_comment_ids TEXT[] NOT NULL DEFAULT '{}',
+        token_budget INTEGER NOT NULL,
+        created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+        FOREIGN KEY (video_id) REFERENCES video_info(yt_id) ON DELETE CASCADE
+    );
+    "#,
+        r#"
+    CREATE INDEX idx_video_summaries_video_id ON video_summaries(video_id, created_at DESC);
+    "#,
+        r#"
+    CREATE TABLE ner_presets (
+        id SERIAL PRIMARY KEY,
+        name VARCHAR UNIQUE NOT NULL,
+        description TEXT,
+        labels TEXT[] NOT NULL,
+        threshold REAL NOT NULL,
+        created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+    );
+    "#,
+        r#"
+    CREATE TABLE comment_annotation_presets (
+        comment_id VARCHAR NOT NULL,
+        preset_id INTEGER NOT NULL,
+        annotated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (comment_id, preset_id),
+        FOREIGN KEY (comment_id) REFERENCES comments(comment_id) ON DELETE CASCADE,
+        FOREIGN KEY (preset_id) REFERENCES ner_presets(id) ON DELETE CASCADE
+    );
+    "#,
+        r#"
+    CREATE INDEX idx_comment_annotation_presets_preset_id ON comment_annotation_presets(preset_id);
+    "#,
+        r#"
+    CREATE TABLE annotation_overrides (
+        comment_id VARCHAR NOT NULL,
+        label VARCHAR NOT NULL,
+        text VARCHAR NOT NULL,
+        action VARCHAR NOT NULL CHECK (action IN ('add', 'remove')),
+        editor VARCHAR NOT NULL,
+        updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (comment_id, label, text)
+    );
+    "#,
+        r#"
+    CREATE TABLE annotation_edits (
+        id SERIAL PRIMARY KEY,
+        comment_id VARCHAR NOT NULL,
+        label VARCHAR NOT NULL,
+        text VARCHAR NOT NULL,
+        action VARCHAR NOT NULL CHECK (action IN ('add', 'remove')),
+        editor VARCHAR NOT NULL,
+        note TEXT,
+        created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+    );
+    "#,
+        r#"
+    CREATE INDEX idx_annotation_edits_comment_id ON annotation_edits(comment_id, created_at);
+    "#,
+        r#"
+    CREATE TABLE webhooks (
+        id SERIAL PRIMARY KEY,
+        url TEXT NOT NULL,
+        secret VARCHAR NOT NULL,
+        events TEXT[] NOT NULL DEFAULT '{}',
+        comment_count_threshold INTEGER NOT NULL DEFAULT 1,
+        active BOOLEAN NOT NULL DEFAULT TRUE,
+        created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+    );
+    "#,
+        r#"
+    CREATE TABLE webhook_deliveries (
+        id SERIAL PRIMARY KEY,
+        webhook_id INTEGER NOT NULL,
+        event VARCHAR NOT NULL,
+        payload JSONB NOT NULL,
+        status VARCHAR NOT NULL CHECK (status IN ('pending', 'delivered', 'failed')),
+        attempts INTEGER NOT NULL DEFAULT 0,
+        response_status INTEGER,
+        last_error TEXT,
+        replay_of INTEGER,
+        created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+        delivered_at TIMESTAMPTZ,
+        FOREIGN KEY (webhook_id) REFERENCES webhooks(id) ON DELETE CASCADE,
+        FOREIGN KEY (replay_of) REFERENCES webhook_deliveries(id) ON DELETE SET NULL
+    );
+    "#,
+        r#"
+    CREATE INDEX idx_webhook_deliveries_webhook_id ON webhook_deliveries(webhook_id, created_at);
+    "#,
+        r#"
+    CREATE TABLE authors (
+        channel_id VARCHAR PRIMARY KEY,
+        display_name VARCHAR NOT NULL,
+        thumbnail TEXT,
+        user_verified BOOLEAN,
+        first_seen_at TIMESTAMPTZ NOT NULL,
+        last_seen_at TIMESTAMPTZ NOT NULL,
+        created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+    );
     "#
     ];
 
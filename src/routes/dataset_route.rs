@@ -0,0 +1,55 @@
+use axum::extract::{State, Query};
+use axum::{http::header, response::{IntoResponse, Response}};
+use serde::Deserialize;
+use crate::db::connection::AppState;
+use crate::ai::dataset::{build_dataset, to_spacy_jsonl, to_conll, to_gliner_json, DatasetFormat, DatasetRequest};
+use crate::routes::errors::AppError;
+
+
+// `video_ids` and `labels` are comma separated
+#[derive(Debug, Deserialize)]
+pub struct DatasetQuery {
+    #[serde(default)]
+    format: DatasetFormat,
+    video_ids: Option<String>,
+    labels: Option<String>,
+    #[serde(default)]
+    verified_only: bool,
+    #[serde(default)]
+    exclude_flagged: bool
+}
+
+pub(crate) fn split_list(value: Option<String>) -> Vec<String> {
+    value
+        .map(|value| value.split(',').map(|item| item.trim().to_string()).filter(|item| !item.is_empty()).collect())
+        .unwrap_or_default()
+}
+
+pub async fn export_ner_dataset(
+    State(app_state): State<AppState>,
+    Query(query): Query<DatasetQuery>
+) -> Result<Response, AppError> {
+    let video_ids = split_list(query.video_ids);
+    let dataset_request = DatasetRequest {
+        video_ids: (!video_ids.is_empty()).then_some(video_ids),
+        labels: split_list(query.labels),
+        verified_only: query.verified_only,
+        exclude_flagged: query.exclude_flagged
+    };
+
+    let examples = build_dataset(&dataset_request, State(app_state)).await?;
+
+    let (content_type, extension, body) = match query.format {
+        DatasetFormat::Spacy => ("application/x-ndjson", "jsonl", to_spacy_jsonl(&examples)),
+        DatasetFormat::Conll => ("text/plain; charset=utf-8", "conll", to_conll(&examples)),
+        DatasetFormat::Gliner => ("application/json", "json", to_gliner_json(&examples))
+    };
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"ner-dataset.{}\"", extension))
+        ],
+        body
+    ).into_response())
+}
@@ -0,0 +1,160 @@
+use axum::{Json, extract::{State, Path, Query}};
+use axum::{http::header, response::{IntoResponse, Response}};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use crate::db::{
+    connection::AppState,
+    models::Pagination,
+    operations::CommentRepository
+};
+use crate::ai::ner::EntityFilter;
+use crate::ai::entity_graph::{build_entity_graph, CoOccurrenceScope};
+use crate::routes::errors::AppError;
+
+
+#[derive(Debug, Deserialize)]
+pub struct EntityCommentsQuery {
+    limit: Option<i64>,
+    offset: Option<i64>,
+    #[serde(default)]
+    exclude_flagged: bool,
+    language: Option<String>
+}
+
+pub async fn get_comments_by_entity(
+    State(app_state): State<AppState>,
+    Path((label, text)): Path<(String, String)>,
+    Query(query): Query<EntityCommentsQuery>
+) -> Result<Json<Value>, AppError> {
+    let filter = EntityFilter::new(&label, &text);
+    let pagination = Pagination { limit: query.limit, offset: query.offset };
+
+    let (comments, total) = CommentRepository::get_by_annotation(
+        &app_state.db_pool,
+        &filter,
+        &pagination,
+        query.exclude_flagged,
+        query.language.as_deref()
+    ).await?;
+
+    let response = json!({
+        "entity": filter,
+        "comments": comments,
+        "count": comments.len(),
+        "total": total,
+        "limit": pagination.limit(),
+        "offset": pagination.offset()
+    });
+
+    Ok(Json(response))
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphFormat {
+    #[default]
+    Json,
+    Graphml,
+    Gexf
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EntityGraphQuery {
+    #[serde(default)]
+    scope: CoOccurrenceScope,
+    min_count: Option<u32>,
+    min_weight: Option<u32>,
+    #[serde(default)]
+    format: GraphFormat,
+    #[serde(default)]
+    exclude_flagged: bool
+}
+
+pub async fn get_entity_graph(
+    State(app_state): State<AppState>,
+    Path(yt_id): Path<String>,
+    Query(query): Query<EntityGraphQuery>
+) -> Result<Response, AppError> {
+    let min_count = query.min_count.unwrap_or(2);
+    let min_weight = query.min_weight.unwrap_or(1);
+
+    let graph = build_entity_graph(&yt_id, &query.scope, &min_count, &min_weight, query.exclude_flagged, State(app_state)).await?;
+
+    let response = match query.format {
+        GraphFormat::Json => Json(graph).into_response(),
+        GraphFormat::Graphml => (
+            [
+                (header::CONTENT_TYPE, "application/graphml+xml".to_string()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}-entities.graphml\"", yt_id))
+            ],
+            graph.to_graphml()
+        ).into_response(),
+        GraphFormat::Gexf => (
+            [
+                (header::CONTENT_TYPE, "application/gexf+xml".to_string()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}-entities.gexf\"", yt_id))
+            ],
+            graph.to_gexf()
+        ).into_response()
+    };
+
+    Ok(response)
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TimelineBucketSize {
+    Hour,
+    #[default]
+    Day,
+    Week,
+    Month
+}
+
+impl TimelineBucketSize {
+    fn as_date_trunc_unit(&self) -> &'static str {
+        match self {
+            TimelineBucketSize::Hour => "hour",
+            TimelineBucketSize::Day => "day",
+            TimelineBucketSize::Week => "week",
+            TimelineBucketSize::Month => "month"
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EntityTimelineQuery {
+    label: String,
+    text: String,
+    #[serde(default)]
+    bucket: TimelineBucketSize,
+    #[serde(default)]
+    exclude_flagged: bool
+}
+
+pub async fn get_entity_timeline(
+    State(app_state): State<AppState>,
+    Path(yt_id): Path<String>,
+    Query(query): Query<EntityTimelineQuery>
+) -> Result<Json<Value>, AppError> {
+    let bucket = query.bucket.as_date_trunc_unit();
+    let filter = EntityFilter::new(&query.label, &query.text);
+
+    let timeline = CommentRepository::get_annotation_timeline(
+        &app_state.db_pool,
+        &yt_id,
+        &filter,
+        bucket,
+        query.exclude_flagged
+    ).await?;
+
+    let response = json!({
+        "video_id": yt_id,
+        "entity": filter,
+        "bucket": bucket,
+        "first_mention": timeline.first().map(|point| point.bucket),
+        "timeline": timeline
+    });
+
+    Ok(Json(response))
+}
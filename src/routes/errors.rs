@@ -10,6 +10,7 @@ pub enum AppError {
     FailedDBConnection(String),
     DatabaseError(String),
     AIServerError(String),
+    ExportError(String),
 }
 
 impl fmt::Display for AppError {
@@ -19,6 +20,7 @@ impl fmt::Display for AppError {
             AppError::FailedDBConnection(msg) => write!(f, "Failed to connect to DB: {}", msg),
             AppError::DatabaseError(msg) => write!(f, "Database error: {}", msg),
             AppError::AIServerError(msg) => write!(f, "AI Server  error: {}", msg),
+            AppError::ExportError(msg) => write!(f, "Export error: {}", msg),
         }
     }
 }
@@ -30,6 +32,7 @@ impl IntoResponse for AppError {
             AppError::FailedDBConnection(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
             AppError::DatabaseError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
             AppError::AIServerError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+            AppError::ExportError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
         };
 
         (status, error_message).into_response()
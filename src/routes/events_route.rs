@@ -0,0 +1,42 @@
+use axum::extract::{State, Query};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use futures_util::{stream, Stream};
+use serde::Deserialize;
+use tokio::sync::broadcast::error::RecvError;
+use crate::db::connection::AppState;
+use crate::routes::dataset_route::split_list;
+
+
+// `video_ids` is comma separated; leaving it out subscribes to every video
+#[derive(Debug, Deserialize)]
+pub struct EventsQuery {
+    video_ids: Option<String>
+}
+
+// Each event's SSE type is its kind (started, progress, completed, failed) and its data is the JSON event
+pub async fn get_events(
+    State(app_state): State<AppState>,
+    Query(query): Query<EventsQuery>
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    let video_ids = split_list(query.video_ids);
+    let receiver = app_state.events.subscribe();
+
+    let events = stream::unfold((receiver, video_ids), |(mut receiver, video_ids)| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) if video_ids.is_empty() || video_ids.contains(&event.video_id) => {
+                    let sse_event = Event::default().event(event.kind.as_str()).json_data(&event);
+                    return Some((sse_event, (receiver, video_ids)));
+                }
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Event subscriber fell behind and skipped {} events", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => return None
+            }
+        }
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
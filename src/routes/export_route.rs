@@ -0,0 +1,250 @@
+use std::io;
+use axum::body::Body;
+use axum::extract::{State, Path, Query};
+use axum::{http::header, response::{IntoResponse, Response}};
+use futures_util::TryStreamExt;
+use serde::Deserialize;
+use crate::db::{
+    connection::AppState,
+    models::{Comment, VideoInfo},
+    operations::{VideoInfoRepository, CommentRepository}
+};
+use crate::ai::ner::build_db_json_as_annotations;
+use crate::utils::export::{Column, ColumnType, ExportEncoder, ExportFormat, ExportValue};
+use crate::utils::stream::channel_body;
+use crate::routes::dataset_route::split_list;
+use crate::routes::errors::AppError;
+
+
+// Rows encoded per chunk; for Parquet this is also the row group size
+const EXPORT_CHUNK_ROWS: usize = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    #[serde(default)]
+    format: ExportFormat,
+    #[serde(default)]
+    exclude_flagged: bool
+}
+
+// `video_ids` is comma separated; leaving it out exports every video
+#[derive(Debug, Deserialize)]
+pub struct BulkExportQuery {
+    #[serde(default)]
+    format: ExportFormat,
+    video_ids: Option<String>,
+    #[serde(default)]
+    exclude_flagged: bool
+}
+
+fn comment_columns(labels: &[String]) -> Vec<Column> {
+    let mut columns = vec![
+        Column::new("comment_id", ColumnType::Text),
+        Column::new("video_id", ColumnType::Text),
+        Column::new("channel_id", ColumnType::Text),
+        Column::new("display_name", ColumnType::Text),
+        Column::new("user_verified", ColumnType::Boolean),
+        Column::new("content", ColumnType::Text),
+        Column::new("published_time", ColumnType::Text),
+        Column::new("published_at", ColumnType::Timestamp),
+        Column::new("published_edited", ColumnType::Boolean),
+        Column::new("language", ColumnType::Text),
+        Column::new("like_count", ColumnType::Integer),
+        Column::new("reply_count", ColumnType::Integer),
+        Column::new("comment_level", ColumnType::Integer),
+        Column::new("reply_to", ColumnType::Text),
+        Column::new("reply_order", ColumnType::Integer)
+    ];
+    columns.extend(labels.iter().map(|label| Column::new(&format!("entities_{}", label), ColumnType::Text)));
+    columns
+}
+
+// Annotations are flattened into one column per label, with the entity texts sorted and joined by "|"
+fn comment_row(comment: &Comment, labels: &[String]) -> Vec<ExportValue> {
+    let annotations = build_db_json_as_annotations(std::slice::from_ref(comment))
+        .pop()
+        .map(|ann_obj| ann_obj.annotations)
+        .unwrap_or_default();
+
+    let mut row = vec![
+        ExportValue::Text(Some(comment.comment_id.clone())),
+        ExportValue::Text(Some(comment.video_id.clone())),
+        ExportValue::Text(Some(comment.channel_id.clone())),
+        ExportValue::Text(Some(comment.display_name.clone())),
+        ExportValue::Boolean(comment.user_verified),
+        ExportValue::Text(Some(comment.content.clone())),
+        ExportValue::Text(comment.published_time.clone()),
+        ExportValue::Timestamp(comment.published_at),
+        ExportValue::Boolean(Some(comment.published_edited)),
+        ExportValue::Text(comment.language.clone()),
+        ExportValue::Integer(comment.like_count.map(i64::from)),
+        ExportValue::Integer(comment.reply_count.map(i64::from)),
+        ExportValue::Integer(comment.comment_level.map(i64::from)),
+        ExportValue::Text(comment.reply_to.clone()),
+        ExportValue::Integer(comment.reply_order.map(i64::from))
+    ];
+    for label in labels {
+        let entities = annotations.iter()
+            .find(|(key, _)| *key == label)
+            .map(|(_, texts)| {
+                let mut texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+                texts.sort_unstable();
+                texts.join("|")
+            });
+        row.push(ExportValue::Text(entities));
+    }
+    row
+}
+
+fn video_columns() -> Vec<Column> {
+    vec![
+        Column::new("yt_id", ColumnType::Text),
+        Column::new("title", ColumnType::Text),
+        Column::new("channel", ColumnType::Text),
+        Column::new("channel_id", ColumnType::Text),
+        Column::new("description", ColumnType::Text),
+        Column::new("views", ColumnType::Integer),
+        Column::new("comment_count", ColumnType::Integer),
+        Column::new("like_count", ColumnType::Integer),
+        Column::new("upload_date", ColumnType::Text),
+        Column::new("uploaded_at", ColumnType::Timestamp),
+        Column::new("created_at", ColumnType::Timestamp)
+    ]
+}
+
+fn video_row(video: &VideoInfo) -> Vec<ExportValue> {
+    vec![
+        ExportValue::Text(Some(video.yt_id.clone())),
+        ExportValue::Text(Some(video.title.clone())),
+        ExportValue::Text(Some(video.channel.clone())),
+        ExportValue::Text(Some(video.channel_id.clone())),
+        ExportValue::Text(video.description.clone()),
+        ExportValue::Integer(Some(video.views)),
+        ExportValue::Integer(Some(video.comment_count)),
+        ExportValue::Integer(Some(video.like_count)),
+        ExportValue::Text(video.upload_date.clone()),
+        ExportValue::Timestamp(video.uploaded_at),
+        ExportValue::Timestamp(video.created_at)
+    ]
+}
+
+fn export_response(format: ExportFormat, filename: &str, body: Body) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.{}\"", filename, format.extension()))
+        ],
+        body
+    ).into_response()
+}
+
+// Rows are read from Postgres and encoded in chunks as the client consumes the body
+async fn stream_comment_export(
+    app_state: AppState,
+    video_ids: Option<Vec<String>>,
+    format: ExportFormat,
+    exclude_flagged: bool,
+    filename: &str
+) -> Result<Response, AppError> {
+    let labels = CommentRepository::get_annotation_labels(&app_state.db_pool, video_ids.as_deref()).await?;
+    let mut encoder = ExportEncoder::new(format, comment_columns(&labels))
+        .map_err(|e| AppError::ExportError(format!("Failed to create {} encoder: {}", format.extension(), e)))?;
+
+    let pool = app_state.db_pool.clone();
+
+    let body = channel_body(|tx| async move {
+        let mut rows = CommentRepository::stream_for_export(&pool, video_ids.as_deref(), exclude_flagged);
+        let mut chunk = Vec::with_capacity(EXPORT_CHUNK_ROWS);
+
+        loop {
+            let next = rows.try_next().await;
+            let done = match next {
+                Ok(Some(comment)) => {
+                    chunk.push(comment_row(&comment, &labels));
+                    false
+                }
+                Ok(None) => true,
+                Err(e) => {
+                    tracing::error!("Comment export failed: {}", e);
+                    let _ = tx.send(Err(io::Error::other(e.to_string()))).await;
+                    return;
+                }
+            };
+            if !done && chunk.len() < EXPORT_CHUNK_ROWS {
+                continue;
+            }
+
+            let encoded = encoder.encode(&chunk);
+            chunk.clear();
+            match encoded {
+                Ok(bytes) if bytes.is_empty() => {}
+                Ok(bytes) => {
+                    // The client went away
+                    if tx.send(Ok(bytes)).await.is_err() {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    let _ = tx.send(Err(e)).await;
+                    return;
+                }
+            }
+            if done {
+                break;
+            }
+        }
+
+        let footer = encoder.finish();
+        if !matches!(&footer, Ok(bytes) if bytes.is_empty()) {
+            let _ = tx.send(footer).await;
+        }
+    });
+
+    Ok(export_response(format, filename, body))
+}
+
+pub async fn export_video_comments(
+    State(app_state): State<AppState>,
+    Path(yt_id): Path<String>,
+    Query(query): Query<ExportQuery>
+) -> Result<Response, AppError> {
+    VideoInfoRepository::get_by_yt_id(&app_state.db_pool, &yt_id).await?
+        .ok_or_else(|| AppError::InvalidInput("Video not found".to_string()))?;
+
+    let filename = format!("{}-comments", yt_id);
+    stream_comment_export(app_state, Some(vec![yt_id]), query.format, query.exclude_flagged, &filename).await
+}
+
+pub async fn export_comments(
+    State(app_state): State<AppState>,
+    Query(query): Query<BulkExportQuery>
+) -> Result<Response, AppError> {
+    let video_ids = split_list(query.video_ids);
+    let video_ids = (!video_ids.is_empty()).then_some(video_ids);
+
+    stream_comment_export(app_state, video_ids, query.format, query.exclude_flagged, "comments").await
+}
+
+// Video metadata is one row per video, so it's encoded in a single pass rather than streamed
+pub async fn export_videos(
+    State(app_state): State<AppState>,
+    Query(query): Query<BulkExportQuery>
+) -> Result<Response, AppError> {
+    let video_ids = split_list(query.video_ids);
+    let videos: Vec<VideoInfo> = VideoInfoRepository::get_all(&app_state.db_pool).await?
+        .into_iter()
+        .filter(|video| video_ids.is_empty() || video_ids.contains(&video.yt_id))
+        .collect();
+    let rows: Vec<Vec<ExportValue>> = videos.iter().map(video_row).collect();
+
+    let encode = || -> io::Result<Vec<u8>> {
+        let mut encoder = ExportEncoder::new(query.format, video_columns())?;
+        let mut bytes = encoder.encode(&rows)?;
+        bytes.extend(encoder.finish()?);
+        Ok(bytes)
+    };
+    let bytes = encode()
+        .map_err(|e| AppError::ExportError(format!("Failed to encode {} export: {}", query.format.extension(), e)))?;
+
+    Ok(export_response(query.format, "videos", Body::from(bytes)))
+}
@@ -3,4 +3,18 @@ pub mod video;
 pub mod database;
 
 pub mod errors;
-pub mod ner_route;
\ No newline at end of file
+pub mod ner_route;
+pub mod entity;
+pub mod sentiment_route;
+pub mod moderation_route;
+pub mod topics_route;
+pub mod search_route;
+pub mod summary_route;
+pub mod preset_route;
+pub mod annotation_route;
+pub mod dataset_route;
+pub mod export_route;
+pub mod events_route;
+pub mod webhook_route;
+pub mod author_route;
+pub mod analytics_route;
@@ -0,0 +1,31 @@
+use axum::{Json, extract::{State, Path}};
+use axum::response::IntoResponse;
+use serde_json::json;
+use crate::db::{
+    connection::AppState,
+    operations::FlagRepository
+};
+use crate::ai::moderation::{moderation_request, ModerationRequest};
+use crate::error::AppError;
+
+
+pub async fn moderation_operation(
+    State(app_state): State<AppState>,
+    Json(payload): Json<ModerationRequest>
+) -> Result<impl IntoResponse, AppError> {
+    let result = moderation_request(payload, State(app_state)).await?;
+    Ok(Json(result))
+}
+
+pub async fn get_video_flags(
+    State(app_state): State<AppState>,
+    Path(yt_id): Path<String>
+) -> Result<impl IntoResponse, AppError> {
+    let flags = FlagRepository::get_by_video_id(&app_state.db_pool, &yt_id).await?;
+
+    Ok(Json(json!({
+        "video_id": yt_id,
+        "flags": flags,
+        "count": flags.len()
+    })))
+}
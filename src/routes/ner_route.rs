@@ -3,34 +3,76 @@ use axum::{Json, extract::{State, Path}};
 use serde::{Deserialize, Serialize};
 use crate::db::{
     connection::AppState,
-    models::{CreateVideoInfoDto, CreateCommentDto},
+    models::{CreateVideoInfoDto, CreateCommentDto, AnnotationScope},
     operations::{VideoInfoRepository, CommentRepository}
 };
 use crate::ai::ner::{
     ner_request,
+    ner_request_counts,
+    start_ner_run,
     build_ranked_annotations,
+    build_aggregated_ranked_annotations,
     RankedAnnotations,
+    RankingMode,
     NERRequest,
     NERRequestResult,
     AnnotationObject
 };
-use axum::{response::IntoResponse};
+use crate::ai::ner_runs::NerRun;
+use axum::{response::{IntoResponse, Response}, http::StatusCode};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
 use crate::error::AppError;
+use crate::routes::errors;
 
 
 pub async fn ner_operation(
     State(app_state): State<AppState>,
     Json(payload): Json<NERRequest>
-) -> Result<impl IntoResponse, AppError> {
+) -> Result<Response, AppError> {
+    if payload.background {
+        let run = start_ner_run(payload, State(app_state)).await?;
+        return Ok((StatusCode::ACCEPTED, Json(run)).into_response());
+    }
+
+    if payload.counts_only {
+        let counts = ner_request_counts(payload, State(app_state)).await?;
+        return Ok(Json(counts).into_response());
+    }
+
     let result = ner_request(payload, State(app_state)).await?;
-    Ok(Json(result))
+    Ok(Json(result).into_response())
+}
+
+pub async fn get_ner_run(
+    State(app_state): State<AppState>,
+    Path(run_id): Path<Uuid>
+) -> Result<Json<NerRun>, errors::AppError> {
+    app_state.ner_runs.get(&run_id).await
+        .map(Json)
+        .ok_or_else(|| errors::AppError::InvalidInput(format!("NER run {} not found", run_id)))
+}
+
+pub async fn cancel_ner_run(
+    State(app_state): State<AppState>,
+    Path(run_id): Path<Uuid>
+) -> Result<Json<NerRun>, errors::AppError> {
+    app_state.ner_runs.cancel(&run_id).await
+        .map(Json)
+        .ok_or_else(|| errors::AppError::InvalidInput(format!("NER run {} not found", run_id)))
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct GetRankedAnnotationsRequest {
     video_id: String,
     #[serde(default)]
-    threshold: Option<u32>
+    threshold: Option<u32>,
+    #[serde(default)]
+    mode: RankingMode,
+    #[serde(default)]
+    exclude_flagged: bool,
+    #[serde(default)]
+    preset: Option<String>
 }
 pub async fn get_ranked_annotations_route(
     State(app_state): State<AppState>,
@@ -39,9 +81,46 @@ pub async fn get_ranked_annotations_route(
     let video_id = payload.video_id;
     let threshold = payload.threshold.unwrap_or(2);
 
-    let ranked_annotations = build_ranked_annotations(&video_id, &threshold, State(app_state)).await?;
+    let ranked_annotations = build_ranked_annotations(&video_id, &threshold, &payload.mode, payload.exclude_flagged, payload.preset.as_deref(), State(app_state)).await?;
 
     Ok(Json(ranked_annotations))    
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct GetAggregatedRankedAnnotationsRequest {
+    #[serde(default)]
+    video_ids: Option<Vec<String>>,
+    #[serde(default)]
+    channel_id: Option<String>,
+    #[serde(default)]
+    published_after: Option<DateTime<Utc>>,
+    #[serde(default)]
+    published_before: Option<DateTime<Utc>>,
+    #[serde(default)]
+    threshold: Option<u32>,
+    #[serde(default)]
+    limit: Option<u32>,
+    #[serde(default)]
+    exclude_flagged: bool,
+    #[serde(default)]
+    preset: Option<String>
+}
+pub async fn get_aggregated_ranked_annotations_route(
+    State(app_state): State<AppState>,
+    Json(payload): Json<GetAggregatedRankedAnnotationsRequest>
+) -> Result<impl IntoResponse, AppError> {
+    let scope = AnnotationScope {
+        video_ids: payload.video_ids,
+        channel_id: payload.channel_id,
+        published_after: payload.published_after,
+        published_before: payload.published_before,
+        exclude_flagged: payload.exclude_flagged,
+        preset: payload.preset
+    };
+    let threshold = payload.threshold.unwrap_or(2);
+    let limit = payload.limit.unwrap_or(50);
+
+    let ranked_annotations = build_aggregated_ranked_annotations(&scope, &threshold, &limit, State(app_state)).await?;
 
+    Ok(Json(ranked_annotations))
+}
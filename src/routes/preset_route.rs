@@ -0,0 +1,87 @@
+use axum::{Json, extract::{State, Path}};
+use serde_json::{json, Value};
+use crate::db::{
+    connection::AppState,
+    models::{NerPreset, CreateNerPresetDto},
+    operations::PresetRepository
+};
+use crate::routes::errors::AppError;
+
+
+fn validate_preset(preset_dto: &CreateNerPresetDto) -> Result<(), AppError> {
+    if preset_dto.name.trim().is_empty() {
+        return Err(AppError::InvalidInput("Preset name cannot be empty".to_string()));
+    }
+    if preset_dto.labels.is_empty() {
+        return Err(AppError::InvalidInput("Preset needs at least one label".to_string()));
+    }
+    if !(0.0..=1.0).contains(&preset_dto.threshold) {
+        return Err(AppError::InvalidInput("Preset threshold must be between 0 and 1".to_string()));
+    }
+    Ok(())
+}
+
+pub async fn create_preset(
+    State(app_state): State<AppState>,
+    Json(payload): Json<CreateNerPresetDto>
+) -> Result<Json<NerPreset>, AppError> {
+    validate_preset(&payload)?;
+
+    if PresetRepository::get_by_name(&app_state.db_pool, &payload.name).await?.is_some() {
+        return Err(AppError::InvalidInput(format!("NER preset '{}' already exists", payload.name)));
+    }
+
+    let preset = PresetRepository::create(&app_state.db_pool, payload).await?;
+    Ok(Json(preset))
+}
+
+pub async fn get_presets(State(app_state): State<AppState>) -> Result<Json<Value>, AppError> {
+    let presets = PresetRepository::get_all(&app_state.db_pool).await?;
+
+    let response = json!({
+        "presets": presets,
+        "count": presets.len()
+    });
+
+    Ok(Json(response))
+}
+
+pub async fn get_preset(
+    State(app_state): State<AppState>,
+    Path(name): Path<String>
+) -> Result<Json<NerPreset>, AppError> {
+    PresetRepository::get_by_name(&app_state.db_pool, &name).await?
+        .map(Json)
+        .ok_or_else(|| AppError::InvalidInput(format!("NER preset '{}' not found", name)))
+}
+
+pub async fn update_preset(
+    State(app_state): State<AppState>,
+    Path(name): Path<String>,
+    Json(payload): Json<CreateNerPresetDto>
+) -> Result<Json<NerPreset>, AppError> {
+    validate_preset(&payload)?;
+
+    if payload.name != name && PresetRepository::get_by_name(&app_state.db_pool, &payload.name).await?.is_some() {
+        return Err(AppError::InvalidInput(format!("NER preset '{}' already exists", payload.name)));
+    }
+
+    PresetRepository::update(&app_state.db_pool, &name, payload).await?
+        .map(Json)
+        .ok_or_else(|| AppError::InvalidInput(format!("NER preset '{}' not found", name)))
+}
+
+pub async fn delete_preset(
+    State(app_state): State<AppState>,
+    Path(name): Path<String>
+) -> Result<Json<Value>, AppError> {
+    let deleted = PresetRepository::delete(&app_state.db_pool, &name).await?;
+    if deleted == 0 {
+        return Err(AppError::InvalidInput(format!("NER preset '{}' not found", name)));
+    }
+
+    Ok(Json(json!({
+        "name": name,
+        "deleted": true
+    })))
+}
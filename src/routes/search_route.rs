@@ -0,0 +1,14 @@
+use axum::{Json, extract::State};
+use axum::response::IntoResponse;
+use crate::db::connection::AppState;
+use crate::ai::search::{semantic_search, SemanticSearchRequest};
+use crate::error::AppError;
+
+
+pub async fn semantic_search_route(
+    State(app_state): State<AppState>,
+    Json(payload): Json<SemanticSearchRequest>
+) -> Result<impl IntoResponse, AppError> {
+    let result = semantic_search(payload, State(app_state)).await?;
+    Ok(Json(result))
+}
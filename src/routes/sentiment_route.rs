@@ -0,0 +1,34 @@
+use axum::{Json, extract::{State, Path, Query}};
+use axum::response::IntoResponse;
+use serde::Deserialize;
+use crate::db::connection::AppState;
+use crate::ai::sentiment::{sentiment_request, build_sentiment_summary, SentimentRequest};
+use crate::error::AppError;
+
+
+pub async fn sentiment_operation(
+    State(app_state): State<AppState>,
+    Json(payload): Json<SentimentRequest>
+) -> Result<impl IntoResponse, AppError> {
+    let result = sentiment_request(payload, State(app_state)).await?;
+    Ok(Json(result))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SentimentSummaryQuery {
+    limit: Option<i64>,
+    #[serde(default)]
+    exclude_flagged: bool
+}
+
+pub async fn get_video_sentiment(
+    State(app_state): State<AppState>,
+    Path(yt_id): Path<String>,
+    Query(query): Query<SentimentSummaryQuery>
+) -> Result<impl IntoResponse, AppError> {
+    let limit = query.limit.unwrap_or(5).clamp(1, 100);
+
+    let summary = build_sentiment_summary(&yt_id, &limit, query.exclude_flagged, State(app_state)).await?;
+
+    Ok(Json(summary))
+}
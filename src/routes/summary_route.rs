@@ -0,0 +1,27 @@
+use axum::{Json, extract::{State, Path}};
+use crate::db::{
+    connection::AppState,
+    models::VideoSummary,
+    operations::SummaryRepository
+};
+use crate::ai::summary::{summarize_video, SummaryRequest};
+use crate::routes::errors::AppError;
+
+
+pub async fn create_video_summary(
+    State(app_state): State<AppState>,
+    Path(yt_id): Path<String>,
+    Json(payload): Json<SummaryRequest>
+) -> Result<Json<VideoSummary>, AppError> {
+    let summary = summarize_video(&yt_id, &payload, State(app_state)).await?;
+    Ok(Json(summary))
+}
+
+pub async fn get_video_summary(
+    State(app_state): State<AppState>,
+    Path(yt_id): Path<String>
+) -> Result<Json<VideoSummary>, AppError> {
+    SummaryRepository::get_latest_by_video_id(&app_state.db_pool, &yt_id).await?
+        .map(Json)
+        .ok_or_else(|| AppError::InvalidInput(format!("No summary stored for video {}", yt_id)))
+}
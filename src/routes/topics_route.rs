@@ -0,0 +1,36 @@
+use axum::{Json, extract::{State, Path, Query}};
+use axum::response::IntoResponse;
+use serde::Deserialize;
+use crate::db::connection::AppState;
+use crate::ai::embeddings::{embedding_request, EmbeddingRequest};
+use crate::ai::topics::build_topics;
+use crate::error::AppError;
+
+
+pub async fn embedding_operation(
+    State(app_state): State<AppState>,
+    Json(payload): Json<EmbeddingRequest>
+) -> Result<impl IntoResponse, AppError> {
+    let result = embedding_request(payload, State(app_state)).await?;
+    Ok(Json(result))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TopicsQuery {
+    clusters: Option<usize>,
+    representatives: Option<usize>,
+    #[serde(default)]
+    exclude_flagged: bool
+}
+
+pub async fn get_video_topics(
+    State(app_state): State<AppState>,
+    Path(yt_id): Path<String>,
+    Query(query): Query<TopicsQuery>
+) -> Result<impl IntoResponse, AppError> {
+    let representatives = query.representatives.unwrap_or(3);
+
+    let topics = build_topics(&yt_id, query.clusters, &representatives, query.exclude_flagged, State(app_state)).await?;
+
+    Ok(Json(topics))
+}
@@ -1,12 +1,21 @@
-use axum::{Json, extract::{State, Path}};
-use serde_json::{json, Value};
+use axum::{Json, extract::{State, Path, Query}};
+use axum::{http::header, response::{IntoResponse, Response}};
+use serde_json::{json, Map, Value};
 use serde::{Deserialize};
+use chrono::Utc;
 use yt_scraper::{YoutubeExtractor};
 use crate::db::{
     connection::AppState,
-    models::{CreateVideoInfoDto, CreateCommentDto},
-    operations::{VideoInfoRepository, CommentRepository}
+    models::{Comment, CreateVideoInfoDto, CreateCommentDto, Pagination},
+    operations::{VideoInfoRepository, CommentRepository, AuthorRepository}
 };
+use crate::ai::ner::EntityFilter;
+use crate::ai::annotation_edits::reapply_overrides;
+use crate::utils::published_time::{parse_published_time, is_edited};
+use crate::utils::language::detect_language;
+use crate::utils::stream::{channel_body, send_json_rows, JsonStreamFormat};
+use crate::events::{Operation, ProgressReporter};
+use crate::webhooks::{self, WebhookEvent};
 use crate::routes::errors::AppError;
 
 
@@ -15,6 +24,30 @@ pub struct VideoRequest {
     video: String
 }
 
+#[derive(Deserialize)]
+pub struct CommentQuery {
+    entity: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    #[serde(default)]
+    exclude_flagged: bool,
+    language: Option<String>,
+    #[serde(default)]
+    format: JsonStreamFormat
+}
+
+#[derive(Deserialize)]
+pub struct VideoQuery {
+    #[serde(default)]
+    exclude_flagged: bool,
+    language: Option<String>,
+    #[serde(default)]
+    format: JsonStreamFormat
+}
+
+
+// Comments are saved in chunks of this size, with a progress event after each
+const SAVE_PROGRESS_CHUNK: usize = 100;
 
 pub async fn video_extraction(
     State(app_state): State<AppState>,
@@ -24,13 +57,48 @@ pub async fn video_extraction(
         return Err(AppError::InvalidInput("Video ID cannot be empty".to_string()));
     }
 
-    let video_id = payload.video;
+    let progress = app_state.events.reporter(Operation::VideoExtraction, &payload.video, None);
+    progress.started(None);
+
+    match extract_video(&payload.video, &app_state, &progress).await {
+        Ok((response, saved)) => {
+            progress.completed(saved, Some(saved));
+            Ok(Json(response))
+        }
+        Err(e) => {
+            progress.failed(0, None, e.to_string());
+            Err(e)
+        }
+    }
+}
+
+async fn save_comments(app_state: &AppState, comment_dtos: Vec<CreateCommentDto>, progress: &ProgressReporter) -> Result<Vec<Comment>, AppError> {
+    let total = comment_dtos.len();
+    let mut saved_comments = Vec::with_capacity(total);
+    let mut comment_dtos = comment_dtos.into_iter().peekable();
+
+    while comment_dtos.peek().is_some() {
+        let chunk: Vec<CreateCommentDto> = comment_dtos.by_ref().take(SAVE_PROGRESS_CHUNK).collect();
+        saved_comments.extend(CommentRepository::create_batch(&app_state.db_pool, chunk).await?);
+        progress.progress("saving", saved_comments.len(), Some(total));
+    }
+
+    let saved_comments = reapply_overrides(&app_state.db_pool, saved_comments).await?;
+    AuthorRepository::upsert_from_comments(&app_state.db_pool, &saved_comments, Utc::now()).await?;
+
+    Ok(saved_comments)
+}
+
+// Returns the response body and the number of comments saved
+async fn extract_video(video_id: &str, app_state: &AppState, progress: &ProgressReporter) -> Result<(Value, usize), AppError> {
     let extractor = YoutubeExtractor::new();
 
-    let (video_info, comments) = extractor.extract(&video_id).await
+    let (video_info, comments) = extractor.extract(video_id).await
         .map_err(|e| AppError::InvalidInput(format!("Failed to extract video: {}", e)))?;
+    let extracted_at = Utc::now();
+    progress.progress("extracted", comments.len(), Some(comments.len()));
 
-    if let Some(_existing_video) = VideoInfoRepository::get_by_yt_id(&app_state.db_pool, &video_info.yt_id).await? {
+    if let Some(existing_video) = VideoInfoRepository::get_by_yt_id(&app_state.db_pool, &video_info.yt_id).await? {
         let updated_video = VideoInfoRepository::update_stats(
             &app_state.db_pool,
             &video_info.yt_id,
@@ -42,6 +110,8 @@ pub async fn video_extraction(
         CommentRepository::delete_by_video_id(&app_state.db_pool, &video_info.yt_id).await?;
         
         let comment_dtos: Vec<CreateCommentDto> = comments.into_iter().map(|comment| {
+            let published = parse_published_time(&comment.published_time, extracted_at);
+            let language = detect_language(&comment.content);
             CreateCommentDto {
                 comment_id: comment.comment_id,
                 channel_id: comment.channel_id,
@@ -50,6 +120,11 @@ pub async fn video_extraction(
                 user_verified: comment.user_verified,
                 thumbnail: comment.thumbnail,
                 content: comment.content,
+                published_at: published.map(|parsed| parsed.at),
+                published_precision: published.map(|parsed| parsed.precision.as_str().to_string()),
+                published_edited: is_edited(&comment.published_time),
+                language: language.as_ref().map(|detected| detected.code.clone()),
+                language_confidence: language.map(|detected| detected.confidence),
                 published_time: comment.published_time,
                 like_count: comment.like_count,
                 reply_count: comment.reply_count,
@@ -60,7 +135,22 @@ pub async fn video_extraction(
             }
         }).collect();
 
-        let saved_comments = CommentRepository::create_batch(&app_state.db_pool, comment_dtos).await?;
+        let saved_comments = save_comments(app_state, comment_dtos, progress).await?;
+
+        webhooks::dispatch(app_state, WebhookEvent::VideoRefreshed, json!({
+            "video_id": updated_video.yt_id,
+            "title": updated_video.title,
+            "comment_count": updated_video.comment_count,
+            "saved_comments": saved_comments.len()
+        }));
+        if updated_video.comment_count != existing_video.comment_count {
+            webhooks::dispatch(app_state, WebhookEvent::CommentCountChanged, json!({
+                "video_id": updated_video.yt_id,
+                "previous": existing_video.comment_count,
+                "current": updated_video.comment_count,
+                "delta": updated_video.comment_count - existing_video.comment_count
+            }));
+        }
         
         let response = json!({
             "status": "updated",
@@ -69,9 +159,10 @@ pub async fn video_extraction(
             "message": format!("Video stats updated and {} comments refreshed", saved_comments.len())
         });
         
-        return Ok(Json(response));
+        return Ok((response, saved_comments.len()));
     }
 
+    let uploaded = parse_published_time(&video_info.upload_date, extracted_at);
     let video_dto = CreateVideoInfoDto {
         title: video_info.title.clone(),
         channel: video_info.channel.clone(),
@@ -83,10 +174,14 @@ pub async fn video_extraction(
         like_count: video_info.like_count,
         video_thumbnail: video_info.video_thumbnail.clone(),
         upload_date: video_info.upload_date.clone(),
+        uploaded_at: uploaded.map(|parsed| parsed.at),
+        upload_date_precision: uploaded.map(|parsed| parsed.precision.as_str().to_string()),
         channel_thumbnail: video_info.channel_thumbnail.clone(),
     };
 
     let comment_dtos: Vec<CreateCommentDto> = comments.into_iter().map(|comment| {
+        let published = parse_published_time(&comment.published_time, extracted_at);
+        let language = detect_language(&comment.content);
         CreateCommentDto {
             comment_id: comment.comment_id,
             channel_id: comment.channel_id,
@@ -95,6 +190,11 @@ pub async fn video_extraction(
             user_verified: comment.user_verified,
             thumbnail: comment.thumbnail,
             content: comment.content,
+            published_at: published.map(|parsed| parsed.at),
+            published_precision: published.map(|parsed| parsed.precision.as_str().to_string()),
+            published_edited: is_edited(&comment.published_time),
+            language: language.as_ref().map(|detected| detected.code.clone()),
+            language_confidence: language.map(|detected| detected.confidence),
             published_time: comment.published_time,
             like_count: comment.like_count,
             reply_count: comment.reply_count,
@@ -107,11 +207,18 @@ pub async fn video_extraction(
 
     let saved_video = VideoInfoRepository::create(&app_state.db_pool, video_dto).await?;
     
-    let saved_comments = CommentRepository::create_batch(&app_state.db_pool, comment_dtos).await?;
+    let saved_comments = save_comments(app_state, comment_dtos, progress).await?;
 
     println!("Saved video: {}", saved_video.title);
     println!("Saved {} comments", saved_comments.len());
 
+    webhooks::dispatch(app_state, WebhookEvent::VideoCreated, json!({
+        "video_id": saved_video.yt_id,
+        "title": saved_video.title,
+        "comment_count": saved_video.comment_count,
+        "saved_comments": saved_comments.len()
+    }));
+
     let response = json!({
         "status": "created",
         "video_info": saved_video,
@@ -119,7 +226,7 @@ pub async fn video_extraction(
         "message": format!("Video and {} comments saved successfully", saved_comments.len())
     });
 
-    Ok(Json(response))
+    Ok((response, saved_comments.len()))
 }
 
 pub async fn get_videos(State(app_state): State<AppState>) -> Result<Json<Value>, AppError> {
@@ -133,39 +240,137 @@ pub async fn get_videos(State(app_state): State<AppState>) -> Result<Json<Value>
     Ok(Json(response))
 }
 
+// Comments are streamed from Postgres into the body rather than collected first
+fn stream_video_comments(
+    app_state: AppState,
+    yt_id: String,
+    head: Map<String, Value>,
+    language: Option<String>,
+    exclude_flagged: bool,
+    format: JsonStreamFormat,
+    count_key: &'static str
+) -> Response {
+    let body = channel_body(|tx| async move {
+        let rows = CommentRepository::stream_by_video_id(&app_state.db_pool, &yt_id, language.as_deref(), exclude_flagged);
+        send_json_rows(&tx, format, head, "comments", count_key, rows).await;
+    });
+
+    ([(header::CONTENT_TYPE, format.content_type())], body).into_response()
+}
+
 pub async fn get_video_by_id(
     State(app_state): State<AppState>,
-    Path(yt_id): Path<String>
-) -> Result<Json<Value>, AppError> {
-    let video = VideoInfoRepository::get_by_yt_id(&app_state.db_pool, &yt_id).await?;
-    
-    match video {
-        Some(v) => {
-            let comments = CommentRepository::get_by_video_id(&app_state.db_pool, &yt_id).await?;
-            
-            let response = json!({
-                "video": v,
-                "comments": comments,
-                "comment_count": comments.len()
-            });
-            
-            Ok(Json(response))
-        }
-        None => Err(AppError::InvalidInput("Video not found".to_string()))
-    }
+    Path(yt_id): Path<String>,
+    Query(query): Query<VideoQuery>
+) -> Result<Response, AppError> {
+    let video = VideoInfoRepository::get_by_yt_id(&app_state.db_pool, &yt_id).await?
+        .ok_or_else(|| AppError::InvalidInput("Video not found".to_string()))?;
+
+    let mut head = Map::new();
+    head.insert("video".to_string(), json!(video));
+
+    Ok(stream_video_comments(app_state, yt_id, head, query.language, query.exclude_flagged, query.format, "comment_count"))
 }
 
 pub async fn get_comments_by_video_id(
     State(app_state): State<AppState>,
-    Path(yt_id): Path<String>
-) -> Result<Json<Value>, AppError> {
-    let comments = CommentRepository::get_by_video_id(&app_state.db_pool, &yt_id).await?;
-    
-    let response = json!({
-        "video_id": yt_id,
-        "comments": comments,
-        "count": comments.len()
-    });
-    
-    Ok(Json(response))
-}
\ No newline at end of file
+    Path(yt_id): Path<String>,
+    Query(query): Query<CommentQuery>
+) -> Result<Response, AppError> {
+    // Entity matches are paginated, so they're small enough to return in one piece
+    if let Some(entity) = query.entity {
+        let filter = EntityFilter::parse(&entity)?;
+        let pagination = Pagination { limit: query.limit, offset: query.offset };
+        let (comments, total) = CommentRepository::get_by_video_id_and_annotation(
+            &app_state.db_pool,
+            &yt_id,
+            &filter,
+            &pagination,
+            query.exclude_flagged,
+            query.language.as_deref()
+        ).await?;
+
+        let response = json!({
+            "video_id": yt_id,
+            "entity": filter,
+            "comments": comments,
+            "count": comments.len(),
+            "total": total,
+            "limit": pagination.limit(),
+            "offset": pagination.offset()
+        });
+
+        return Ok(Json(response).into_response());
+    }
+
+    let mut head = Map::new();
+    head.insert("video_id".to_string(), json!(yt_id));
+
+    Ok(stream_video_comments(app_state, yt_id, head, query.language, query.exclude_flagged, query.format, "count"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::PgPool;
+    use crate::ai::annotation_edits::{edit_annotations, AnnotationEditRequest};
+    use crate::db::connection::insert_test_video;
+
+    fn comment_dto(comment_id: &str, annotations: Value) -> CreateCommentDto {
+        CreateCommentDto {
+            comment_id: comment_id.to_string(),
+            channel_id: format!("channel-{}", comment_id),
+            video_id: "video".to_string(),
+            display_name: comment_id.to_string(),
+            user_verified: false,
+            thumbnail: String::new(),
+            content: "Elon Musk was at the Tesla launch".to_string(),
+            published_time: "a day ago".to_string(),
+            published_at: None,
+            published_precision: None,
+            published_edited: false,
+            language: None,
+            language_confidence: None,
+            like_count: 0,
+            reply_count: 0,
+            comment_level: 0,
+            reply_to: String::new(),
+            reply_order: 0,
+            annotations
+        }
+    }
+
+    #[sqlx::test]
+    async fn annotation_edits_survive_a_refresh(pool: PgPool) {
+        insert_test_video(&pool, "video").await;
+        let app_state = AppState::for_tests(pool);
+        let progress = app_state.events.reporter(Operation::VideoExtraction, "video", None);
+
+        save_comments(&app_state, vec![
+            comment_dto("edited", json!({ "person": ["Elon"], "org": ["Tesla"] })),
+            comment_dto("untouched", json!({ "org": ["Tesla"] }))
+        ], &progress).await.unwrap();
+
+        let edit: AnnotationEditRequest = serde_json::from_value(json!({
+            "editor": "analyst",
+            "add": { "person": ["Elon Musk"] },
+            "remove": { "person": ["Elon"] }
+        })).unwrap();
+        edit_annotations("edited", edit, State(app_state.clone())).await.unwrap();
+
+        // What the refresh branch of `extract_video` does with freshly scraped comments
+        CommentRepository::delete_by_video_id(&app_state.db_pool, "video").await.unwrap();
+        let refreshed = save_comments(&app_state, vec![
+            comment_dto("edited", json!({})),
+            comment_dto("untouched", json!({}))
+        ], &progress).await.unwrap();
+
+        let edited = refreshed.iter().find(|comment| comment.comment_id == "edited").unwrap();
+        assert_eq!(edited.annotations, Some(json!({ "person": ["Elon Musk"] })));
+
+        let stored = CommentRepository::get_by_comment_id(&app_state.db_pool, "edited").await.unwrap().unwrap();
+        assert_eq!(stored.annotations, Some(json!({ "person": ["Elon Musk"] })));
+        let untouched = CommentRepository::get_by_comment_id(&app_state.db_pool, "untouched").await.unwrap().unwrap();
+        assert_eq!(untouched.annotations, Some(json!({})));
+    }
+}
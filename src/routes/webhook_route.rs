@@ -0,0 +1,108 @@
+use axum::{Json, extract::{State, Path, Query}};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+use crate::db::{
+    connection::AppState,
+    models::{CreateWebhookDto, Pagination, WebhookDelivery},
+    operations::WebhookRepository
+};
+use crate::webhooks::{replay_delivery, WebhookEvent};
+use crate::routes::errors::AppError;
+
+
+fn validate_webhook(webhook_dto: &CreateWebhookDto) -> Result<(), AppError> {
+    if !(webhook_dto.url.starts_with("http://") || webhook_dto.url.starts_with("https://")) {
+        return Err(AppError::InvalidInput("Webhook URL must start with http:// or https://".to_string()));
+    }
+    if let Some(event) = webhook_dto.events.iter().find(|event| WebhookEvent::parse(event).is_none()) {
+        let known: Vec<&str> = WebhookEvent::ALL.iter().map(WebhookEvent::as_str).collect();
+        return Err(AppError::InvalidInput(format!("Unknown webhook event '{}', expected one of: {}", event, known.join(", "))));
+    }
+    if webhook_dto.comment_count_threshold.is_some_and(|threshold| threshold < 1) {
+        return Err(AppError::InvalidInput("Comment count threshold must be at least 1".to_string()));
+    }
+    if webhook_dto.secret.as_deref().is_some_and(|secret| secret.trim().is_empty()) {
+        return Err(AppError::InvalidInput("Webhook secret cannot be empty".to_string()));
+    }
+    Ok(())
+}
+
+// The secret is only included in this response
+pub async fn create_webhook(
+    State(app_state): State<AppState>,
+    Json(payload): Json<CreateWebhookDto>
+) -> Result<Json<Value>, AppError> {
+    validate_webhook(&payload)?;
+
+    let secret = payload.secret.clone()
+        .unwrap_or_else(|| format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()));
+    let webhook = WebhookRepository::create(&app_state.db_pool, payload, &secret).await?;
+
+    Ok(Json(json!({
+        "webhook": webhook,
+        "secret": secret
+    })))
+}
+
+pub async fn get_webhooks(State(app_state): State<AppState>) -> Result<Json<Value>, AppError> {
+    let webhooks = WebhookRepository::get_all(&app_state.db_pool).await?;
+
+    let response = json!({
+        "webhooks": webhooks,
+        "count": webhooks.len()
+    });
+
+    Ok(Json(response))
+}
+
+pub async fn delete_webhook(
+    State(app_state): State<AppState>,
+    Path(id): Path<i32>
+) -> Result<Json<Value>, AppError> {
+    let deleted = WebhookRepository::delete(&app_state.db_pool, id).await?;
+    if deleted == 0 {
+        return Err(AppError::InvalidInput(format!("Webhook {} not found", id)));
+    }
+
+    Ok(Json(json!({
+        "id": id,
+        "deleted": true
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeliveriesQuery {
+    limit: Option<i64>,
+    offset: Option<i64>
+}
+
+pub async fn get_webhook_deliveries(
+    State(app_state): State<AppState>,
+    Path(id): Path<i32>,
+    Query(query): Query<DeliveriesQuery>
+) -> Result<Json<Value>, AppError> {
+    WebhookRepository::get_by_id(&app_state.db_pool, id).await?
+        .ok_or_else(|| AppError::InvalidInput(format!("Webhook {} not found", id)))?;
+
+    let pagination = Pagination { limit: query.limit, offset: query.offset };
+    let deliveries = WebhookRepository::get_deliveries(&app_state.db_pool, id, &pagination).await?;
+
+    let response = json!({
+        "webhook_id": id,
+        "deliveries": deliveries,
+        "count": deliveries.len(),
+        "limit": pagination.limit(),
+        "offset": pagination.offset()
+    });
+
+    Ok(Json(response))
+}
+
+pub async fn replay_webhook_delivery(
+    State(app_state): State<AppState>,
+    Path(delivery_id): Path<i32>
+) -> Result<Json<WebhookDelivery>, AppError> {
+    let delivery = replay_delivery(delivery_id, &app_state).await?;
+    Ok(Json(delivery))
+}
@@ -0,0 +1,209 @@
+use std::io;
+use std::sync::Arc;
+use arrow_array::{ArrayRef, BooleanArray, Int64Array, RecordBatch, StringArray, TimestampMicrosecondArray};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use chrono::{DateTime, SecondsFormat, Utc};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+
+#[derive(Debug, Clone, Copy, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Jsonl,
+    Parquet
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Jsonl => "application/x-ndjson",
+            ExportFormat::Parquet => "application/vnd.apache.parquet"
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Parquet => "parquet"
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColumnType {
+    Text,
+    Integer,
+    Boolean,
+    Timestamp
+}
+
+#[derive(Debug, Clone)]
+pub struct Column {
+    pub name: String,
+    pub column_type: ColumnType
+}
+
+impl Column {
+    pub fn new(name: &str, column_type: ColumnType) -> Self {
+        Column { name: name.to_string(), column_type }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ExportValue {
+    Text(Option<String>),
+    Integer(Option<i64>),
+    Boolean(Option<bool>),
+    Timestamp(Option<DateTime<Utc>>)
+}
+
+impl ExportValue {
+    fn to_csv_field(&self) -> String {
+        match self {
+            ExportValue::Text(value) => value.clone().unwrap_or_default(),
+            ExportValue::Integer(value) => value.map(|v| v.to_string()).unwrap_or_default(),
+            ExportValue::Boolean(value) => value.map(|v| v.to_string()).unwrap_or_default(),
+            ExportValue::Timestamp(value) => value.map(|v| v.to_rfc3339_opts(SecondsFormat::Secs, true)).unwrap_or_default()
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        match self {
+            ExportValue::Text(value) => value.clone().map(Value::String).unwrap_or(Value::Null),
+            ExportValue::Integer(value) => value.map(Value::from).unwrap_or(Value::Null),
+            ExportValue::Boolean(value) => value.map(Value::Bool).unwrap_or(Value::Null),
+            ExportValue::Timestamp(value) => value.map(|v| Value::String(v.to_rfc3339_opts(SecondsFormat::Secs, true))).unwrap_or(Value::Null)
+        }
+    }
+}
+
+fn parquet_schema(columns: &[Column]) -> Arc<Schema> {
+    let fields: Vec<Field> = columns.iter()
+        .map(|column| {
+            let data_type = match column.column_type {
+                ColumnType::Text => DataType::Utf8,
+                ColumnType::Integer => DataType::Int64,
+                ColumnType::Boolean => DataType::Boolean,
+                ColumnType::Timestamp => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()))
+            };
+            Field::new(&column.name, data_type, true)
+        })
+        .collect();
+    Arc::new(Schema::new(fields))
+}
+
+// Values that don't match the column's declared type are written as nulls
+fn parquet_batch(schema: &Arc<Schema>, columns: &[Column], rows: &[Vec<ExportValue>]) -> io::Result<RecordBatch> {
+    let arrays: Vec<ArrayRef> = columns.iter()
+        .enumerate()
+        .map(|(index, column)| {
+            let values = rows.iter().map(move |row| row.get(index));
+            let array: ArrayRef = match column.column_type {
+                ColumnType::Text => Arc::new(values.map(|value| match value {
+                    Some(ExportValue::Text(text)) => text.clone(),
+                    _ => None
+                }).collect::<StringArray>()),
+                ColumnType::Integer => Arc::new(values.map(|value| match value {
+                    Some(ExportValue::Integer(number)) => *number,
+                    _ => None
+                }).collect::<Int64Array>()),
+                ColumnType::Boolean => Arc::new(values.map(|value| match value {
+                    Some(ExportValue::Boolean(flag)) => *flag,
+                    _ => None
+                }).collect::<BooleanArray>()),
+                ColumnType::Timestamp => Arc::new(values.map(|value| match value {
+                    Some(ExportValue::Timestamp(at)) => at.map(|at| at.timestamp_micros()),
+                    _ => None
+                }).collect::<TimestampMicrosecondArray>().with_timezone("UTC"))
+            };
+            array
+        })
+        .collect();
+
+    RecordBatch::try_new(schema.clone(), arrays).map_err(io::Error::other)
+}
+
+// Rows per Parquet row group. The writer buffers rows until a group fills, so this bounds the memory
+// a Parquet export holds regardless of how many rows each `encode` call gets
+const PARQUET_ROW_GROUP_ROWS: usize = 64 * 1024;
+
+// Encodes rows chunk by chunk so exports can be streamed. Each call returns the bytes that are
+// ready to send; for Parquet that's every completed row group, and the footer comes from `finish`.
+pub enum ExportEncoder {
+    Csv { columns: Vec<Column>, header_written: bool },
+    Jsonl { columns: Vec<Column> },
+    Parquet { columns: Vec<Column>, schema: Arc<Schema>, writer: Box<ArrowWriter<Vec<u8>>> }
+}
+
+impl ExportEncoder {
+    pub fn new(format: ExportFormat, columns: Vec<Column>) -> io::Result<Self> {
+        let encoder = match format {
+            ExportFormat::Csv => ExportEncoder::Csv { columns, header_written: false },
+            ExportFormat::Jsonl => ExportEncoder::Jsonl { columns },
+            ExportFormat::Parquet => {
+                let schema = parquet_schema(&columns);
+                let properties = WriterProperties::builder()
+                    .set_compression(Compression::SNAPPY)
+                    .set_max_row_group_size(PARQUET_ROW_GROUP_ROWS)
+                    .build();
+                let writer = ArrowWriter::try_new(Vec::new(), schema.clone(), Some(properties))
+                    .map_err(io::Error::other)?;
+                ExportEncoder::Parquet { columns, schema, writer: Box::new(writer) }
+            }
+        };
+        Ok(encoder)
+    }
+
+    pub fn encode(&mut self, rows: &[Vec<ExportValue>]) -> io::Result<Vec<u8>> {
+        match self {
+            ExportEncoder::Csv { columns, header_written } => {
+                let mut writer = csv::WriterBuilder::new().has_headers(false).from_writer(Vec::new());
+                if !*header_written {
+                    writer.write_record(columns.iter().map(|column| column.name.as_str()))?;
+                    *header_written = true;
+                }
+                for row in rows {
+                    writer.write_record(row.iter().map(|value| value.to_csv_field()))?;
+                }
+                writer.into_inner().map_err(|e| io::Error::other(e.to_string()))
+            }
+            ExportEncoder::Jsonl { columns } => {
+                let mut bytes = Vec::new();
+                for row in rows {
+                    let object: Map<String, Value> = columns.iter()
+                        .zip(row)
+                        .map(|(column, value)| (column.name.clone(), value.to_json()))
+                        .collect();
+                    serde_json::to_writer(&mut bytes, &object)?;
+                    bytes.push(b'\n');
+                }
+                Ok(bytes)
+            }
+            ExportEncoder::Parquet { columns, schema, writer } => {
+                if !rows.is_empty() {
+                    let batch = parquet_batch(schema, columns, rows)?;
+                    writer.write(&batch).map_err(io::Error::other)?;
+                }
+                Ok(std::mem::take(writer.inner_mut()))
+            }
+        }
+    }
+
+    pub fn finish(self) -> io::Result<Vec<u8>> {
+        match self {
+            ExportEncoder::Csv { header_written: false, columns } => {
+                ExportEncoder::Csv { columns, header_written: false }.encode(&[])
+            }
+            ExportEncoder::Csv { .. } | ExportEncoder::Jsonl { .. } => Ok(Vec::new()),
+            ExportEncoder::Parquet { writer, .. } => writer.into_inner().map_err(io::Error::other)
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+
+// `code` is the ISO 639-3 code reported by whatlang, e.g. "eng", "spa", "deu"
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DetectedLanguage {
+    pub code: String,
+    pub confidence: f32
+}
+
+// Returns None unless whatlang considers the result reliable, which rules out most
+// one- or two-word comments rather than guessing a language for them
+pub fn detect_language(text: &str) -> Option<DetectedLanguage> {
+    let info = whatlang::detect(text)?;
+
+    info.is_reliable().then(|| DetectedLanguage {
+        code: info.lang().code().to_string(),
+        confidence: info.confidence() as f32
+    })
+}
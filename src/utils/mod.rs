@@ -0,0 +1,4 @@
+pub mod published_time;
+pub mod language;
+pub mod export;
+pub mod stream;
@@ -0,0 +1,180 @@
+use chrono::{DateTime, Duration, Months, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+
+// How vague a parsed time is: "1 year ago" only pins the time down to within a year
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimePrecision {
+    Second,
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year
+}
+
+impl TimePrecision {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimePrecision::Second => "second",
+            TimePrecision::Minute => "minute",
+            TimePrecision::Hour => "hour",
+            TimePrecision::Day => "day",
+            TimePrecision::Week => "week",
+            TimePrecision::Month => "month",
+            TimePrecision::Year => "year"
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ParsedTime {
+    pub at: DateTime<Utc>,
+    pub precision: TimePrecision
+}
+
+const ABSOLUTE_DATE_FORMATS: [&str; 4] = ["%b %d, %Y", "%B %d, %Y", "%Y-%m-%d", "%d %b %Y"];
+
+pub fn is_edited(value: &str) -> bool {
+    value.to_lowercase().contains("(edited)")
+}
+
+// Parses YouTube's free-form times, either relative ("3 days ago", "2 weeks ago (edited)") or
+// absolute ("Mar 3, 2024", "Premiered Mar 3, 2024"). Relative times are subtracted from `anchor`,
+// which should be when the comment or video was extracted.
+pub fn parse_published_time(value: &str, anchor: DateTime<Utc>) -> Option<ParsedTime> {
+    parse_relative_time(value, anchor).or_else(|| parse_absolute_time(value))
+}
+
+fn parse_relative_time(value: &str, anchor: DateTime<Utc>) -> Option<ParsedTime> {
+    let value = value.to_lowercase();
+    let tokens: Vec<&str> = value.split_whitespace().collect();
+    let ago = tokens.iter().position(|token| *token == "ago")?;
+    if ago < 2 {
+        return None;
+    }
+
+    let amount: u32 = match tokens[ago - 2] {
+        "a" | "an" => 1,
+        number => number.parse().ok()?
+    };
+    let unit = tokens[ago - 1].trim_end_matches('s');
+
+    let (at, precision) = match unit {
+        "second" => (anchor.checked_sub_signed(Duration::seconds(amount as i64))?, TimePrecision::Second),
+        "minute" => (anchor.checked_sub_signed(Duration::minutes(amount as i64))?, TimePrecision::Minute),
+        "hour" => (anchor.checked_sub_signed(Duration::hours(amount as i64))?, TimePrecision::Hour),
+        "day" => (anchor.checked_sub_signed(Duration::days(amount as i64))?, TimePrecision::Day),
+        "week" => (anchor.checked_sub_signed(Duration::weeks(amount as i64))?, TimePrecision::Week),
+        "month" => (anchor.checked_sub_months(Months::new(amount))?, TimePrecision::Month),
+        "year" => (anchor.checked_sub_months(Months::new(amount.checked_mul(12)?))?, TimePrecision::Year),
+        _ => return None
+    };
+
+    Some(ParsedTime { at, precision })
+}
+
+// Tries every suffix of the string so prefixes like "Premiered" or "Streamed live on" are skipped
+fn parse_absolute_time(value: &str) -> Option<ParsedTime> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(value.trim()) {
+        return Some(ParsedTime { at: at.with_timezone(&Utc), precision: TimePrecision::Second });
+    }
+
+    let tokens: Vec<&str> = value.split_whitespace().collect();
+    for start in 0..tokens.len() {
+        let candidate = tokens[start..].join(" ");
+        for format in ABSOLUTE_DATE_FORMATS {
+            if let Ok(date) = NaiveDate::parse_from_str(&candidate, format) {
+                let at = date.and_hms_opt(0, 0, 0)?.and_utc();
+                return Some(ParsedTime { at, precision: TimePrecision::Day });
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn anchor() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 15, 12, 0, 0).unwrap()
+    }
+
+    fn relative(value: &str) -> Option<DateTime<Utc>> {
+        parse_relative_time(value, anchor()).map(|parsed| parsed.at)
+    }
+
+    #[test]
+    fn parses_numeric_relative_times() {
+        assert_eq!(relative("3 weeks ago"), Some(anchor() - Duration::weeks(3)));
+        assert_eq!(relative("2 months ago"), Some(Utc.with_ymd_and_hms(2024, 4, 15, 12, 0, 0).unwrap()));
+        assert_eq!(relative("1 year ago"), Some(Utc.with_ymd_and_hms(2023, 6, 15, 12, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn parses_a_and_an_as_one() {
+        assert_eq!(relative("a day ago"), Some(anchor() - Duration::days(1)));
+        assert_eq!(relative("An hour ago"), Some(anchor() - Duration::hours(1)));
+    }
+
+    #[test]
+    fn ignores_the_edited_suffix() {
+        assert_eq!(relative("5 minutes ago (edited)"), Some(anchor() - Duration::minutes(5)));
+    }
+
+    #[test]
+    fn rejects_unparseable_relative_times() {
+        for value in ["", "ago", "3 fortnights ago", "many days ago"] {
+            assert!(relative(value).is_none(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn reports_the_precision_of_relative_times() {
+        let expected = [
+            ("10 seconds ago", TimePrecision::Second),
+            ("5 minutes ago (edited)", TimePrecision::Minute),
+            ("An hour ago", TimePrecision::Hour),
+            ("a day ago", TimePrecision::Day),
+            ("3 weeks ago", TimePrecision::Week),
+            ("2 months ago", TimePrecision::Month),
+            ("1 year ago", TimePrecision::Year)
+        ];
+        for (value, precision) in expected {
+            assert_eq!(parse_published_time(value, anchor()).map(|parsed| parsed.precision), Some(precision), "{}", value);
+        }
+    }
+
+    #[test]
+    fn parses_absolute_dates_with_prefixes() {
+        let expected = Utc.with_ymd_and_hms(2024, 3, 3, 0, 0, 0).unwrap();
+        for value in ["Mar 3, 2024", "Premiered Mar 3, 2024", "Streamed live on March 3, 2024", "2024-03-03", "3 Mar 2024"] {
+            let parsed = parse_published_time(value, anchor()).unwrap_or_else(|| panic!("failed to parse '{}'", value));
+            assert_eq!(parsed.at, expected, "{}", value);
+            assert_eq!(parsed.precision, TimePrecision::Day, "{}", value);
+        }
+
+        let parsed = parse_published_time("2024-03-03T10:30:00Z", anchor()).unwrap();
+        assert_eq!(parsed.at, Utc.with_ymd_and_hms(2024, 3, 3, 10, 30, 0).unwrap());
+        assert_eq!(parsed.precision, TimePrecision::Second);
+    }
+
+    #[test]
+    fn rejects_unparseable_times() {
+        for value in ["", "yesterday", "3 fortnights ago", "Mar 2024"] {
+            assert!(parse_published_time(value, anchor()).is_none(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn detects_edited_comments() {
+        assert!(is_edited("2 days ago (edited)"));
+        assert!(is_edited("2 days ago (Edited)"));
+        assert!(!is_edited("2 days ago"));
+    }
+}
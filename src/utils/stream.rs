@@ -0,0 +1,112 @@
+use std::future::Future;
+use std::io;
+use axum::body::Body;
+use futures_util::{stream, stream::BoxStream, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tokio::sync::mpsc;
+use crate::routes::errors::AppError;
+
+
+// Buffered bytes are sent once they pass this size
+const FLUSH_BYTES: usize = 64 * 1024;
+
+pub type ChunkSender = mpsc::Sender<io::Result<Vec<u8>>>;
+
+// Runs `produce` in a background task and streams whatever it sends as the response body.
+// The channel is bounded, so a slow client holds back the producer instead of filling memory.
+// Errors after the headers have gone out can only end the body early, so an `Err` aborts it.
+pub fn channel_body<F, Fut>(produce: F) -> Body
+where
+    F: FnOnce(ChunkSender) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static
+{
+    let (tx, rx) = mpsc::channel::<io::Result<Vec<u8>>>(4);
+    tokio::spawn(produce(tx));
+
+    Body::from_stream(stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    }))
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum JsonStreamFormat {
+    // The same object the buffered endpoint returned, written incrementally
+    #[default]
+    Json,
+    // `head` on the first line when it isn't empty, then one row per line
+    Ndjson
+}
+
+impl JsonStreamFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            JsonStreamFormat::Json => "application/json",
+            JsonStreamFormat::Ndjson => "application/x-ndjson"
+        }
+    }
+}
+
+// For `Json` this writes `{...head, "<rows_key>": [rows], "<count_key>": n}`, so the body matches
+// what `json!` would have built from a `Vec` without ever holding the rows at once.
+// Returns false when the stream was aborted or the client went away.
+pub async fn send_json_rows<T: Serialize>(
+    tx: &ChunkSender,
+    format: JsonStreamFormat,
+    head: Map<String, Value>,
+    rows_key: &str,
+    count_key: &str,
+    mut rows: BoxStream<'_, Result<T, AppError>>
+) -> bool {
+    let mut buffer = Vec::with_capacity(FLUSH_BYTES);
+    let mut count: usize = 0;
+
+    match format {
+        JsonStreamFormat::Json => {
+            // Reopen the serialised head so the rows can follow its last field
+            let mut object = Value::Object(head).to_string();
+            object.pop();
+            if object.len() > 1 {
+                object.push(',');
+            }
+            buffer.extend(format!("{}{}:[", object, Value::from(rows_key)).into_bytes());
+        }
+        JsonStreamFormat::Ndjson if head.is_empty() => {}
+        JsonStreamFormat::Ndjson => buffer.extend(format!("{}\n", Value::Object(head)).into_bytes())
+    }
+
+    loop {
+        let row = match rows.try_next().await {
+            Ok(Some(row)) => row,
+            Ok(None) => break,
+            Err(e) => {
+                tracing::error!("Streaming {} failed: {}", rows_key, e);
+                let _ = tx.send(Err(io::Error::other(e.to_string()))).await;
+                return false;
+            }
+        };
+
+        if format == JsonStreamFormat::Json && count > 0 {
+            buffer.push(b',');
+        }
+        if let Err(e) = serde_json::to_writer(&mut buffer, &row) {
+            let _ = tx.send(Err(io::Error::other(e))).await;
+            return false;
+        }
+        if format == JsonStreamFormat::Ndjson {
+            buffer.push(b'\n');
+        }
+        count += 1;
+
+        if buffer.len() >= FLUSH_BYTES && tx.send(Ok(std::mem::take(&mut buffer))).await.is_err() {
+            return false;
+        }
+    }
+
+    if format == JsonStreamFormat::Json {
+        buffer.extend(format!("],{}:{}}}", Value::from(count_key), count).into_bytes());
+    }
+
+    buffer.is_empty() || tx.send(Ok(buffer)).await.is_ok()
+}
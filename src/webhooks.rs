@@ -0,0 +1,309 @@
+use std::time::Duration;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use crate::db::{
+    connection::AppState,
+    models::{Webhook, WebhookDelivery},
+    operations::WebhookRepository
+};
+use crate::routes::errors::AppError;
+
+
+const MAX_ATTEMPTS: i32 = 5;
+// Doubles after each failed attempt: 1s, 2s, 4s, 8s
+#[cfg(not(test))]
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+#[cfg(test)]
+const INITIAL_BACKOFF: Duration = Duration::from_millis(10);
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+pub enum WebhookEvent {
+    #[serde(rename = "video.created")]
+    VideoCreated,
+    #[serde(rename = "video.refreshed")]
+    VideoRefreshed,
+    #[serde(rename = "video.comment_count_changed")]
+    CommentCountChanged,
+    #[serde(rename = "annotation_run.finished")]
+    AnnotationRunFinished
+}
+
+impl WebhookEvent {
+    pub const ALL: [WebhookEvent; 4] = [
+        WebhookEvent::VideoCreated,
+        WebhookEvent::VideoRefreshed,
+        WebhookEvent::CommentCountChanged,
+        WebhookEvent::AnnotationRunFinished
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::VideoCreated => "video.created",
+            WebhookEvent::VideoRefreshed => "video.refreshed",
+            WebhookEvent::CommentCountChanged => "video.comment_count_changed",
+            WebhookEvent::AnnotationRunFinished => "annotation_run.finished"
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|event| event.as_str() == value)
+    }
+}
+
+// Hex HMAC-SHA256 of "{timestamp}.{body}". Receivers should recompute it with their secret and
+// reject old timestamps, so a captured delivery can't be replayed against them later.
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+// Comment count changes only go to webhooks whose threshold the change reaches
+fn wants(webhook: &Webhook, event: WebhookEvent, data: &Value) -> bool {
+    if event != WebhookEvent::CommentCountChanged {
+        return true;
+    }
+    let delta = data.get("delta").and_then(Value::as_i64).unwrap_or(0);
+    delta != 0 && delta.abs() >= i64::from(webhook.comment_count_threshold)
+}
+
+// Runs in the background so the triggering request never waits on, or fails because of, a receiver.
+// Retries are scheduled in memory, so deliveries still pending at shutdown stay 'pending' in the log.
+pub fn dispatch(app_state: &AppState, event: WebhookEvent, data: Value) {
+    let app_state = app_state.clone();
+
+    tokio::spawn(async move {
+        let webhooks = match WebhookRepository::get_subscribed(&app_state.db_pool, event.as_str()).await {
+            Ok(webhooks) => webhooks,
+            Err(e) => {
+                tracing::error!("Failed to load webhooks for {}: {}", event.as_str(), e);
+                return;
+            }
+        };
+
+        let payload = json!({
+            "event": event,
+            "created_at": Utc::now(),
+            "data": data
+        });
+        let client = reqwest::Client::new();
+
+        for webhook in webhooks.into_iter().filter(|webhook| wants(webhook, event, &data)) {
+            match WebhookRepository::create_delivery(&app_state.db_pool, webhook.id, event.as_str(), &payload, None).await {
+                Ok(delivery) => {
+                    let (app_state, client) = (app_state.clone(), client.clone());
+                    tokio::spawn(async move { deliver(&app_state, &client, &webhook, &delivery).await });
+                }
+                Err(e) => tracing::error!("Failed to log delivery for webhook {}: {}", webhook.id, e)
+            }
+        }
+    });
+}
+
+async fn attempt(client: &reqwest::Client, webhook: &Webhook, delivery: &WebhookDelivery, body: &[u8]) -> Result<i32, (Option<i32>, String)> {
+    let timestamp = Utc::now().timestamp();
+
+    let response = client
+        .post(&webhook.url)
+        .timeout(DELIVERY_TIMEOUT)
+        .header("Content-Type", "application/json")
+        .header("X-Webhook-Event", &delivery.event)
+        .header("X-Webhook-Delivery", delivery.id.to_string())
+        .header("X-Webhook-Timestamp", timestamp.to_string())
+        .header("X-Webhook-Signature", format!("sha256={}", sign(&webhook.secret, timestamp, body)))
+        .body(body.to_vec())
+        .send()
+        .await
+        .map_err(|e| (None, e.to_string()))?;
+
+    let status = i32::from(response.status().as_u16());
+    if response.status().is_success() {
+        Ok(status)
+    } else {
+        Err((Some(status), format!("Receiver responded with {}", response.status())))
+    }
+}
+
+async fn deliver(app_state: &AppState, client: &reqwest::Client, webhook: &Webhook, delivery: &WebhookDelivery) {
+    let body = delivery.payload.to_string().into_bytes();
+    let mut backoff = INITIAL_BACKOFF;
+
+    for attempt_number in 1..=MAX_ATTEMPTS {
+        let (status, response_status, error) = match attempt(client, webhook, delivery, &body).await {
+            Ok(response_status) => ("delivered", Some(response_status), None),
+            Err((response_status, error)) if attempt_number == MAX_ATTEMPTS => ("failed", response_status, Some(error)),
+            Err((response_status, error)) => ("pending", response_status, Some(error))
+        };
+
+        if let Err(e) = WebhookRepository::record_attempt(&app_state.db_pool, delivery.id, status, response_status, error).await {
+            tracing::error!("Failed to record attempt for delivery {}: {}", delivery.id, e);
+        }
+        if status != "pending" {
+            return;
+        }
+
+        tokio::time::sleep(backoff).await;
+        backoff *= 2;
+    }
+}
+
+// Sends the original payload again as a new delivery, so the log keeps both
+pub async fn replay_delivery(delivery_id: i32, app_state: &AppState) -> Result<WebhookDelivery, AppError> {
+    let original = WebhookRepository::get_delivery(&app_state.db_pool, delivery_id).await?
+        .ok_or_else(|| AppError::InvalidInput(format!("Webhook delivery {} not found", delivery_id)))?;
+    let webhook = WebhookRepository::get_by_id(&app_state.db_pool, original.webhook_id).await?
+        .ok_or_else(|| AppError::InvalidInput(format!("Webhook {} not found", original.webhook_id)))?;
+
+    let delivery = WebhookRepository::create_delivery(
+        &app_state.db_pool,
+        webhook.id,
+        &original.event,
+        &original.payload,
+        Some(original.id)
+    ).await?;
+
+    let (app_state, replayed) = (app_state.clone(), delivery.clone());
+    tokio::spawn(async move { deliver(&app_state, &reqwest::Client::new(), &webhook, &replayed).await });
+
+    Ok(delivery)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use axum::{Router, body::Bytes, extract::State, http::{HeaderMap, StatusCode}, routing::post};
+    use sqlx::PgPool;
+    use tokio::net::TcpListener;
+    use crate::db::models::{CreateWebhookDto, Pagination};
+
+    const SECRET: &str = "test-secret";
+
+    type ReceivedDeliveries = Arc<Mutex<Vec<(HeaderMap, Bytes)>>>;
+
+    // Keeps every delivery it receives and answers all of them with `status`
+    async fn start_receiver(status: StatusCode) -> (String, ReceivedDeliveries) {
+        let received: ReceivedDeliveries = Arc::default();
+        let app = Router::new()
+            .route("/hook", post(move |State(received): State<ReceivedDeliveries>, headers: HeaderMap, body: Bytes| async move {
+                received.lock().unwrap().push((headers, body));
+                status
+            }))
+            .with_state(received.clone());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        (format!("http://{}/hook", address), received)
+    }
+
+    async fn setup(pool: PgPool, url: String) -> (AppState, Webhook) {
+        let app_state = AppState::for_tests(pool);
+        let webhook_dto = CreateWebhookDto { url, secret: None, events: Vec::new(), comment_count_threshold: None };
+        let webhook = WebhookRepository::create(&app_state.db_pool, webhook_dto, SECRET).await.unwrap();
+        (app_state, webhook)
+    }
+
+    // Deliveries run in the background, so poll the log until the delivery leaves 'pending'
+    async fn wait_until_finished(app_state: &AppState, delivery_id: i32) -> WebhookDelivery {
+        for _ in 0..200 {
+            let delivery = WebhookRepository::get_delivery(&app_state.db_pool, delivery_id).await.unwrap().unwrap();
+            if delivery.status != "pending" {
+                return delivery;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("Delivery {} is still pending", delivery_id);
+    }
+
+    fn header<'a>(headers: &'a HeaderMap, name: &str) -> &'a str {
+        headers.get(name).unwrap().to_str().unwrap()
+    }
+
+    #[sqlx::test]
+    async fn receivers_can_verify_the_signature(pool: PgPool) {
+        let (url, received) = start_receiver(StatusCode::OK).await;
+        let (app_state, webhook) = setup(pool, url).await;
+
+        dispatch(&app_state, WebhookEvent::VideoCreated, json!({ "video_id": "video" }));
+
+        let mut logged = Vec::new();
+        for _ in 0..200 {
+            logged = WebhookRepository::get_deliveries(&app_state.db_pool, webhook.id, &Pagination::default()).await.unwrap();
+            if !logged.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(logged.len(), 1, "dispatch should log one delivery");
+        let delivery = wait_until_finished(&app_state, logged[0].id).await;
+        assert_eq!(delivery.status, "delivered");
+        assert_eq!(delivery.webhook_id, webhook.id);
+        assert_eq!(delivery.attempts, 1);
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        let (headers, body) = &received[0];
+        let timestamp: i64 = header(headers, "X-Webhook-Timestamp").parse().unwrap();
+        let signature = header(headers, "X-Webhook-Signature");
+
+        assert_eq!(signature, format!("sha256={}", sign(SECRET, timestamp, body)));
+        assert_ne!(signature, format!("sha256={}", sign("another-secret", timestamp, body)));
+        assert_ne!(signature, format!("sha256={}", sign(SECRET, timestamp + 1, body)));
+        assert_eq!(header(headers, "X-Webhook-Event"), "video.created");
+        assert_eq!(header(headers, "X-Webhook-Delivery"), delivery.id.to_string());
+
+        let payload: Value = serde_json::from_slice(body).unwrap();
+        assert_eq!(payload["event"], "video.created");
+        assert_eq!(payload["data"], json!({ "video_id": "video" }));
+    }
+
+    #[sqlx::test]
+    async fn failing_receivers_are_retried_until_the_delivery_fails(pool: PgPool) {
+        let (url, received) = start_receiver(StatusCode::INTERNAL_SERVER_ERROR).await;
+        let (app_state, webhook) = setup(pool, url).await;
+
+        let delivery = WebhookRepository::create_delivery(&app_state.db_pool, webhook.id, "video.created", &json!({}), None).await.unwrap();
+        deliver(&app_state, &reqwest::Client::new(), &webhook, &delivery).await;
+
+        let delivery = WebhookRepository::get_delivery(&app_state.db_pool, delivery.id).await.unwrap().unwrap();
+        assert_eq!(delivery.status, "failed");
+        assert_eq!(delivery.attempts, MAX_ATTEMPTS);
+        assert_eq!(delivery.response_status, Some(500));
+        assert!(delivery.last_error.is_some());
+        assert!(delivery.delivered_at.is_none());
+        assert_eq!(received.lock().unwrap().len(), MAX_ATTEMPTS as usize);
+    }
+
+    #[sqlx::test]
+    async fn replays_create_a_linked_delivery(pool: PgPool) {
+        let (url, received) = start_receiver(StatusCode::OK).await;
+        let (app_state, webhook) = setup(pool, url).await;
+
+        let payload = json!({ "event": "video.refreshed", "data": { "video_id": "video" } });
+        let original = WebhookRepository::create_delivery(&app_state.db_pool, webhook.id, "video.refreshed", &payload, None).await.unwrap();
+        deliver(&app_state, &reqwest::Client::new(), &webhook, &original).await;
+
+        let replayed = replay_delivery(original.id, &app_state).await.unwrap();
+        assert_ne!(replayed.id, original.id);
+        assert_eq!(replayed.replay_of, Some(original.id));
+        assert_eq!(replayed.payload, payload);
+
+        let replayed = wait_until_finished(&app_state, replayed.id).await;
+        assert_eq!(replayed.status, "delivered");
+        let original = WebhookRepository::get_delivery(&app_state.db_pool, original.id).await.unwrap().unwrap();
+        assert_eq!((original.status.as_str(), original.attempts, original.replay_of), ("delivered", 1, None));
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0].1, received[1].1);
+        assert_eq!(header(&received[1].0, "X-Webhook-Delivery"), replayed.id.to_string());
+    }
+}